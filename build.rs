@@ -3,6 +3,15 @@ fn main() {
         println!("cargo:rustc-link-lib=dylib=dbghelp");
     }
 
+    // The test binary exports `__jit_debug_descriptor` like JIT hosts do, as
+    // it is looked up in the dynamic symbols
+    if matches!(
+        std::env::var("CARGO_CFG_TARGET_OS").as_deref(),
+        Ok("linux" | "android")
+    ) {
+        println!("cargo:rustc-link-arg-bin=test=-Wl,--export-dynamic");
+    }
+
     // Detect iOS simulator target
     if let Ok(target) = std::env::var("TARGET") {
         // iOS simulator targets contain "-sim" suffix
@@ -14,7 +14,7 @@ mod linux {
             LINUX_GATE_LIBRARY_NAME,
         },
         nix::{
-            sys::mman::{mmap_anonymous, MapFlags, ProtFlags},
+            sys::mman::{mmap, mmap_anonymous, mprotect, MapFlags, ProtFlags},
             unistd::getppid,
         },
     };
@@ -366,6 +366,284 @@ mod linux {
         }
     }
 
+    fn spawn_anon_elf_wait() -> Result<()> {
+        let page_size = nix::unistd::sysconf(nix::unistd::SysconfVar::PAGE_SIZE)
+            .unwrap()
+            .unwrap() as usize;
+        let memory_size = std::num::NonZeroUsize::new(4 * page_size).unwrap();
+        // Copy the start of our own ELF image into anonymous memory, like a
+        // custom loader would
+        let image = std::fs::read("/proc/self/exe")?;
+        let mapped_mem = unsafe {
+            let mapped_mem = mmap_anonymous(
+                None,
+                memory_size,
+                ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+                MapFlags::MAP_PRIVATE | MapFlags::MAP_ANON,
+            )?;
+            let len = image.len().min(memory_size.get());
+            std::ptr::copy_nonoverlapping(image.as_ptr(), mapped_mem.as_ptr().cast(), len);
+            // The headers are readable, the rest is executable, so that the
+            // image spans two mappings
+            mprotect(mapped_mem, page_size, ProtFlags::PROT_READ)?;
+            mprotect(
+                mapped_mem.byte_add(page_size),
+                memory_size.get() - page_size,
+                ProtFlags::PROT_READ | ProtFlags::PROT_EXEC,
+            )?;
+            mapped_mem
+        };
+
+        println!("{} {}", mapped_mem.as_ptr() as usize, memory_size);
+        loop {
+            std::thread::park();
+        }
+    }
+
+    fn map_exec_file_wait(path: &str) -> Result<()> {
+        let file = std::fs::File::open(path)?;
+        let size =
+            std::num::NonZeroUsize::new(file.metadata()?.len() as usize).ok_or("empty file")?;
+        let mapped_mem = unsafe {
+            mmap(
+                None,
+                size,
+                ProtFlags::PROT_READ | ProtFlags::PROT_EXEC,
+                MapFlags::MAP_PRIVATE,
+                &file,
+                0,
+            )?
+        };
+
+        println!("1");
+        loop {
+            std::thread::park();
+            // Keep the mapping alive.
+            println!("{:p}", mapped_mem);
+        }
+    }
+
+    // The GDB JIT interface, see "JIT Compilation Interface" in the GDB manual
+    #[repr(C)]
+    struct JitCodeEntry {
+        next_entry: *mut JitCodeEntry,
+        prev_entry: *mut JitCodeEntry,
+        symfile_addr: *const u8,
+        symfile_size: u64,
+    }
+
+    #[repr(C)]
+    struct JitDescriptor {
+        version: u32,
+        action_flag: u32,
+        relevant_entry: *mut JitCodeEntry,
+        first_entry: *mut JitCodeEntry,
+    }
+
+    #[no_mangle]
+    static mut __jit_debug_descriptor: JitDescriptor = JitDescriptor {
+        version: 1,
+        action_flag: 0,
+        relevant_entry: std::ptr::null_mut(),
+        first_entry: std::ptr::null_mut(),
+    };
+
+    #[no_mangle]
+    #[inline(never)]
+    extern "C" fn __jit_debug_register_code() {}
+
+    /// Build the ELF object a JIT registers with GDB, with a single function
+    /// `name` at `address`, in a `.text` section at `address`.
+    fn jit_object(address: usize, size: usize, name: &str) -> Vec<u8> {
+        use goblin::{
+            container::Ctx,
+            elf::{header, section_header, sym, Header, SectionHeader, Sym},
+        };
+        use scroll::Pwrite;
+
+        let ctx = Ctx::default();
+        let shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0";
+        let strtab = [b"\0", name.as_bytes(), b"\0"].concat();
+        let syms = [
+            Sym::default(),
+            Sym {
+                st_name: 1,
+                st_info: (sym::STB_GLOBAL << 4) | sym::STT_FUNC,
+                st_shndx: 1,
+                st_value: address as u64,
+                st_size: 0x80,
+                ..Default::default()
+            },
+        ];
+
+        let sym_size = Sym::size(ctx.container);
+        let symtab_offset = Header::size(ctx).next_multiple_of(8);
+        let strtab_offset = symtab_offset + syms.len() * sym_size;
+        let shstrtab_offset = strtab_offset + strtab.len();
+        let sections = [
+            SectionHeader::default(),
+            SectionHeader {
+                sh_name: 1,
+                sh_type: section_header::SHT_NOBITS,
+                sh_flags: (section_header::SHF_ALLOC | section_header::SHF_EXECINSTR) as u64,
+                sh_addr: address as u64,
+                sh_size: size as u64,
+                ..Default::default()
+            },
+            SectionHeader {
+                sh_name: 7,
+                sh_type: section_header::SHT_SYMTAB,
+                sh_offset: symtab_offset as u64,
+                sh_size: (syms.len() * sym_size) as u64,
+                sh_link: 3,
+                sh_info: 1,
+                sh_entsize: sym_size as u64,
+                ..Default::default()
+            },
+            SectionHeader {
+                sh_name: 15,
+                sh_type: section_header::SHT_STRTAB,
+                sh_offset: strtab_offset as u64,
+                sh_size: strtab.len() as u64,
+                ..Default::default()
+            },
+            SectionHeader {
+                sh_name: 23,
+                sh_type: section_header::SHT_STRTAB,
+                sh_offset: shstrtab_offset as u64,
+                sh_size: shstrtab.len() as u64,
+                ..Default::default()
+            },
+        ];
+        let section_headers_offset = (shstrtab_offset + shstrtab.len()).next_multiple_of(8);
+
+        let mut elf_header = Header::new(ctx);
+        elf_header.e_type = header::ET_REL;
+        elf_header.e_shoff = section_headers_offset as u64;
+        elf_header.e_shentsize = SectionHeader::size(ctx) as u16;
+        elf_header.e_shnum = sections.len() as u16;
+        elf_header.e_shstrndx = 4;
+
+        let mut data =
+            vec![0u8; section_headers_offset + sections.len() * SectionHeader::size(ctx)];
+        data.pwrite_with(elf_header, 0, ctx.le).unwrap();
+        for (i, sym) in syms.into_iter().enumerate() {
+            data.pwrite_with(sym, symtab_offset + i * sym_size, ctx)
+                .unwrap();
+        }
+        data.pwrite(&strtab[..], strtab_offset).unwrap();
+        data.pwrite(&shstrtab[..], shstrtab_offset).unwrap();
+        for (i, section) in sections.into_iter().enumerate() {
+            data.pwrite_with(
+                section,
+                section_headers_offset + i * SectionHeader::size(ctx),
+                ctx,
+            )
+            .unwrap();
+        }
+        data
+    }
+
+    /// Run a `SIGUSR1` handler on an alternate signal stack that never
+    /// returns. Prints the range of the alternate stack, and the newline once
+    /// the handler runs.
+    fn spawn_signal_handler_wait() -> Result<()> {
+        extern "C" fn handler(
+            _signal: libc::c_int,
+            _info: *mut libc::siginfo_t,
+            _context: *mut libc::c_void,
+        ) {
+            unsafe {
+                libc::write(1, b"\n".as_ptr().cast(), 1);
+                loop {
+                    libc::pause();
+                }
+            }
+        }
+
+        let altstack_size = 64 * 1024;
+        let altstack = vec![0u8; altstack_size].leak();
+        unsafe {
+            let stack = libc::stack_t {
+                ss_sp: altstack.as_mut_ptr().cast(),
+                ss_flags: 0,
+                ss_size: altstack_size,
+            };
+            test!(
+                libc::sigaltstack(&stack, std::ptr::null_mut()) == 0,
+                "sigaltstack failed"
+            );
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handler as *const () as usize;
+            action.sa_flags = libc::SA_ONSTACK | libc::SA_SIGINFO;
+            test!(
+                libc::sigaction(libc::SIGUSR1, &action, std::ptr::null_mut()) == 0,
+                "sigaction failed"
+            );
+        }
+
+        print!("{} {}", altstack.as_ptr() as usize, altstack_size);
+        std::io::Write::flush(&mut std::io::stdout())?;
+        unsafe { libc::raise(libc::SIGUSR1) };
+        unreachable!("the handler doesn't return");
+    }
+
+    fn spawn_jit_code_wait() -> Result<()> {
+        let page_size = nix::unistd::sysconf(nix::unistd::SysconfVar::PAGE_SIZE)
+            .unwrap()
+            .unwrap() as usize;
+        let page = std::num::NonZeroUsize::new(page_size).unwrap();
+        let (perf_code, other_code) = unsafe {
+            (
+                mmap_anonymous(
+                    None,
+                    page,
+                    ProtFlags::PROT_READ | ProtFlags::PROT_EXEC,
+                    MapFlags::MAP_PRIVATE | MapFlags::MAP_ANON,
+                )?,
+                mmap_anonymous(
+                    None,
+                    page.saturating_mul(std::num::NonZeroUsize::new(2).unwrap()),
+                    ProtFlags::PROT_READ | ProtFlags::PROT_WRITE,
+                    MapFlags::MAP_PRIVATE | MapFlags::MAP_ANON,
+                )?,
+            )
+        };
+        let perf_code = perf_code.as_ptr() as usize;
+        let other_code = other_code.as_ptr() as usize;
+
+        // Describe the first mapping in the perf map, as well as a function of
+        // the other one, which the GDB JIT interface covers...
+        std::fs::write(
+            format!("/tmp/perf-{}.map", std::process::id()),
+            format!(
+                "{perf_code:x} 40 jit_perf_func\n{:x} 10 jit_perf_covered_func\n",
+                other_code + 0x100
+            ),
+        )?;
+
+        // ...and the first page of the other one with the GDB JIT interface
+        let symfile = jit_object(other_code, page_size, "jit_gdb_func").leak();
+        let entry = Box::leak(Box::new(JitCodeEntry {
+            next_entry: std::ptr::null_mut(),
+            prev_entry: std::ptr::null_mut(),
+            symfile_addr: symfile.as_ptr(),
+            symfile_size: symfile.len() as u64,
+        }));
+        unsafe {
+            let descriptor = std::ptr::addr_of_mut!(__jit_debug_descriptor);
+            (*descriptor).first_entry = entry;
+            (*descriptor).relevant_entry = entry;
+            (*descriptor).action_flag = 1; // JIT_REGISTER_FN
+        }
+        __jit_debug_register_code();
+
+        println!("{perf_code} {other_code} {page_size}");
+        loop {
+            std::thread::park();
+        }
+    }
+
     fn spawn_alloc_wait() -> Result<()> {
         let page_size = nix::unistd::sysconf(nix::unistd::SysconfVar::PAGE_SIZE).unwrap();
         let memory_size = page_size.unwrap() as usize;
@@ -381,6 +659,25 @@ mod linux {
         }
     }
 
+    fn spawn_thread_pointer_wait() -> Result<()> {
+        // With glibc, the thread pointer points to the `struct pthread` of the
+        // thread on x86_64
+        println!("{:#x}", unsafe { libc::pthread_self() });
+        loop {
+            std::thread::park();
+        }
+    }
+
+    fn spawn_global_wait() -> Result<()> {
+        // Zero initialized, so in `.bss`
+        static GLOBAL: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
+        GLOBAL.store(0x0123_4567_89ab_cdef, std::sync::atomic::Ordering::SeqCst);
+        println!("{:p}", &GLOBAL);
+        loop {
+            std::thread::park();
+        }
+    }
+
     fn create_files_wait(num: usize) -> Result<()> {
         let mut file_array = Vec::<tempfile::NamedTempFile>::with_capacity(num);
         for id in 0..num {
@@ -401,6 +698,45 @@ mod linux {
         }
     }
 
+    fn create_sockets_wait() -> Result<()> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        println!("1");
+        loop {
+            std::thread::park();
+            // Keep the listening socket open.
+            println!("{:?}", listener.local_addr());
+        }
+    }
+
+    /// Dump this process from its panic hook when a thread panics.
+    fn panic_dump(path: &str) -> Result<()> {
+        use minidump_writer::{
+            minidump_writer::MinidumpWriter,
+            panic_hook::{dump_current_process, install_panic_hook},
+        };
+
+        let path = path.to_owned();
+        install_panic_hook(move |rust_panic| {
+            let mut file = std::fs::File::create(&path).expect("failed to create the dump file");
+            let result = dump_current_process(
+                MinidumpWriter::new(std::process::id() as _, rust_panic.thread_id)
+                    .set_rust_panic(rust_panic.clone()),
+                &mut file,
+            );
+            if let Err(e) = result {
+                eprintln!("failed to dump the panic: {e}");
+            }
+        });
+
+        let panicked = std::thread::Builder::new()
+            .name("panicking".into())
+            .spawn(|| panic!("the answer is {}", 42))?
+            .join()
+            .is_err();
+        test!(panicked, "the thread didn't panic");
+        Ok(())
+    }
+
     pub(super) fn real_main(args: Vec<String>) -> Result<()> {
         match args.len() {
             1 => match args[0].as_ref() {
@@ -412,6 +748,12 @@ mod linux {
                 "linux_gate_mapping_id" => test_linux_gate_mapping_id(),
                 "spawn_mmap_wait" => spawn_mmap_wait(),
                 "spawn_alloc_wait" => spawn_alloc_wait(),
+                "spawn_anon_elf_wait" => spawn_anon_elf_wait(),
+                "spawn_jit_code_wait" => spawn_jit_code_wait(),
+                "spawn_signal_handler_wait" => spawn_signal_handler_wait(),
+                "spawn_thread_pointer_wait" => spawn_thread_pointer_wait(),
+                "spawn_global_wait" => spawn_global_wait(),
+                "create_sockets_wait" => create_sockets_wait(),
                 _ => Err("Len 1: Unknown test option".into()),
             },
             2 => match args[0].as_ref() {
@@ -427,6 +769,8 @@ mod linux {
                     let num_of_files: usize = args[1].parse().unwrap();
                     create_files_wait(num_of_files)
                 }
+                "map_exec_file_wait" => map_exec_file_wait(&args[1]),
+                "panic_dump" => panic_dump(&args[1]),
                 _ => Err(format!("Len 2: Unknown test option: {}", args[0]).into()),
             },
             3 => {
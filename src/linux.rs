@@ -6,16 +6,24 @@ mod android;
 pub mod app_memory;
 pub(crate) mod auxv;
 pub mod crash_context;
+pub mod data_segments;
 mod dso_debug;
 mod dumper_cpu_info;
 pub mod errors;
+pub mod ext_streams;
+pub mod jit_code;
 pub mod maps_reader;
 pub mod mem_reader;
+pub mod memory_rules;
 pub mod minidump_writer;
 pub mod module_reader;
+pub mod panic_hook;
 pub mod ptrace_dumper;
+pub mod register_memory;
+pub mod sanitization;
 pub(crate) mod sections;
 mod serializers;
+pub mod signal_frame;
 pub mod thread_info;
 
 pub use maps_reader::LINUX_GATE_LIBRARY_NAME;
@@ -1,6 +1,6 @@
 // These entries store a list of memory regions that the client wants included
 // in the minidump.
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
 pub struct AppMemory {
     pub ptr: usize,
     pub length: usize,
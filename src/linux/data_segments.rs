@@ -0,0 +1,35 @@
+//! The data segments of modules, see [`DataSegmentsPolicy`].
+
+/// Which modules to include the writable segments (`.data`, `.bss`, `.got`...)
+/// of in the memory list, like `MiniDumpWithDataSegs` on Windows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSegmentsPolicy {
+    /// Include the data segments of the main executable
+    pub main_executable: bool,
+    /// Include the data segments of the modules whose name matches one of
+    /// these patterns, see [`MappingInfo::name_matches`]
+    ///
+    /// [`MappingInfo::name_matches`]: crate::linux::maps_reader::MappingInfo::name_matches
+    pub module_patterns: Vec<String>,
+    /// The maximum number of bytes to include for all modules, the segments
+    /// past it are truncated
+    pub max_size: usize,
+}
+
+impl Default for DataSegmentsPolicy {
+    fn default() -> Self {
+        Self {
+            main_executable: true,
+            module_patterns: Vec::new(),
+            max_size: 16 * 1024 * 1024,
+        }
+    }
+}
+
+impl DataSegmentsPolicy {
+    /// Include the data segments of the modules whose name matches `pattern`.
+    pub fn include_modules(&mut self, pattern: impl Into<String>) -> &mut Self {
+        self.module_patterns.push(pattern.into());
+        self
+    }
+}
@@ -73,11 +73,32 @@ pub struct RDebug {
     r_ldbase: ElfAddr, /* Base address the linker is loaded at.  */
 }
 
-pub fn write_dso_debug_stream(
-    buffer: &mut Buffer,
-    blamed_thread: i32,
-    auxv: &AuxvDumpInfo,
-) -> Result<MDRawDirectory> {
+/// An object in the dynamic linker's `link_map` chain.
+#[derive(Debug, Clone)]
+pub struct LinkMapEntry {
+    /// The load bias of the object, `l_addr`
+    pub addr: usize,
+    /// The file name of the object, `l_name`. Empty for the main executable.
+    pub name: String,
+    /// The address of the object's dynamic section, `l_ld`
+    pub ld: usize,
+}
+
+/// The state the dynamic linker exposes to debuggers through `r_debug`.
+#[derive(Debug)]
+pub struct DsoDebug {
+    r_debug: RDebug,
+    /// The address of the program's dynamic section
+    dynamic: ElfAddr,
+    /// The size of the program's dynamic section, up to its `DT_NULL` entry
+    dynamic_length: usize,
+    /// The loaded objects, in the order of the `r_map` chain
+    pub link_map: Vec<LinkMapEntry>,
+}
+
+/// Read `r_debug` and the `link_map` chain of the process through its
+/// `DT_DEBUG` dynamic entry.
+pub fn read_dso_debug(blamed_thread: i32, auxv: &AuxvDumpInfo) -> Result<DsoDebug> {
     let phnum_max =
         auxv.get_program_header_count()
             .ok_or(SectionDsoDebugError::CouldNotFind("AT_PHNUM in auxv"))? as usize;
@@ -183,9 +204,43 @@ pub fn write_dso_debug_stream(
         let map = &body[0];
 
         curr_map = map.l_next;
-        dso_vec.push(map.clone());
+
+        let mut name = String::new();
+        if map.l_name > 0 {
+            let name_data = PtraceDumper::copy_from_process(blamed_thread, map.l_name, 256)?;
+
+            // C - string is NULL-terminated
+            if let Some(n) = name_data.splitn(2, |x| *x == b'\0').next() {
+                name = String::from_utf8(n.to_vec())?;
+            }
+        }
+        dso_vec.push(LinkMapEntry {
+            addr: map.l_addr as usize,
+            name,
+            ld: map.l_ld,
+        });
     }
 
+    Ok(DsoDebug {
+        r_debug: debug_entry.clone(),
+        dynamic: dyn_addr,
+        dynamic_length,
+        link_map: dso_vec,
+    })
+}
+
+pub fn write_dso_debug_stream(
+    buffer: &mut Buffer,
+    blamed_thread: i32,
+    auxv: &AuxvDumpInfo,
+) -> Result<MDRawDirectory> {
+    let DsoDebug {
+        r_debug: debug_entry,
+        dynamic: dyn_addr,
+        dynamic_length,
+        link_map: dso_vec,
+    } = read_dso_debug(blamed_thread, auxv)?;
+
     let mut linkmap_rva = u32::MAX;
     if !dso_vec.is_empty() {
         // If we have at least one DSO, create an array of MDRawLinkMap
@@ -195,21 +250,11 @@ pub fn write_dso_debug_stream(
 
         // Iterate over DSOs and write their information to mini dump
         for (idx, map) in dso_vec.iter().enumerate() {
-            let mut filename = String::new();
-            if map.l_name > 0 {
-                let filename_data =
-                    PtraceDumper::copy_from_process(blamed_thread, map.l_name, 256)?;
-
-                // C - string is NULL-terminated
-                if let Some(name) = filename_data.splitn(2, |x| *x == b'\0').next() {
-                    filename = String::from_utf8(name.to_vec())?;
-                }
-            }
-            let location = write_string_to_location(buffer, &filename)?;
+            let location = write_string_to_location(buffer, &map.name)?;
             let entry = MDRawLinkMap {
-                addr: map.l_addr,
+                addr: map.addr as ElfAddr,
                 name: location.rva,
-                ld: map.l_ld as ElfAddr,
+                ld: map.ld as ElfAddr,
             };
 
             linkmap.set_value_at(buffer, entry, idx)?;
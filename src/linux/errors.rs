@@ -223,6 +223,48 @@ pub enum SectionMappingsError {
     MemoryWriterError(#[from] MemoryWriterError),
     #[error("Failed to get effective path of mapping ({0:?})")]
     GetEffectivePathError(MappingInfo, #[source] MapsReaderError),
+    #[error("Failed to read the link_map, falling back to the mappings")]
+    LinkMapUnavailable(#[source] SectionDsoDebugError),
+    #[error("{name:?} is in the link_map, but its dynamic section at {ld:#x} isn't mapped")]
+    LinkMapEntryNotMapped { name: String, ld: usize },
+    #[error("{name:?} mapped at {start_address:#x} isn't in the link_map")]
+    MappingNotInLinkMap { name: String, start_address: usize },
+    #[error("Failed to read the perf map")]
+    ReadPerfMapFailed(#[source] JitCodeError),
+    #[error("Failed to read the GDB JIT interface")]
+    ReadGdbJitFailed(#[source] JitCodeError),
+    #[error("Errors occurred while reading the GDB JIT interface")]
+    GdbJitErrors(#[source] ErrorList<JitCodeError>),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum JitCodeError {
+    #[error("Failed to read the perf map {0}")]
+    ReadPerfMapFailed(
+        String,
+        #[source]
+        #[serde(serialize_with = "serialize_io_error")]
+        std::io::Error,
+    ),
+    #[error("Failed to read the GDB JIT descriptor at {0:#x}")]
+    ReadDescriptorFailed(usize, #[source] DumperError),
+    #[error("Unsupported GDB JIT descriptor version {0}")]
+    UnsupportedDescriptorVersion(u32),
+    #[error("Failed to read the GDB JIT entry at {0:#x}")]
+    ReadEntryFailed(usize, #[source] DumperError),
+    #[error("Failed to read the GDB JIT object at {0:#x}")]
+    ReadObjectFailed(usize, #[source] DumperError),
+    #[error("Failed to parse the GDB JIT object at {0:#x}")]
+    ParseObjectFailed(
+        usize,
+        #[source]
+        #[serde(serialize_with = "serialize_goblin_error")]
+        goblin::error::Error,
+    ),
+    #[error(
+        "The {2:#x} bytes at {1:#x} in the GDB JIT object at {0:#x} overflow the address space"
+    )]
+    RangeOverflow(usize, u64, u64),
 }
 
 #[derive(Debug, Error, serde::Serialize)]
@@ -237,12 +279,134 @@ pub enum SectionMemInfoListError {
     ),
 }
 
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionSmapsError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+    #[error("Failed to read from procfs")]
+    ProcfsError(
+        #[from]
+        #[serde(serialize_with = "serialize_proc_error")]
+        procfs_core::ProcError,
+    ),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionContainerInfoError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+    #[error("Failed to read from procfs")]
+    ProcfsError(
+        #[from]
+        #[serde(serialize_with = "serialize_proc_error")]
+        procfs_core::ProcError,
+    ),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionSecurityContextError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+    #[error("Failed to read /proc/<pid>/status")]
+    IOError(
+        #[from]
+        #[serde(serialize_with = "serialize_io_error")]
+        std::io::Error,
+    ),
+    #[error("Failed to parse /proc/<pid>/status")]
+    ProcfsError(
+        #[from]
+        #[serde(serialize_with = "serialize_proc_error")]
+        procfs_core::ProcError,
+    ),
+}
+
 #[derive(Debug, Error, serde::Serialize)]
 pub enum SectionMemListError {
     #[error("Failed to write to memory")]
     MemoryWriterError(#[from] MemoryWriterError),
 }
 
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionModuleInfoError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionDumpReasonError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionRustPanicError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionStackOverflowError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionSignalFramesError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionJitSymbolsError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionModuleMetadataError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+    #[error("Failed to read the ELF metadata of the module at {0:#x}")]
+    ReadMetadataFailed(usize, #[source] DumperError),
+    #[error("Failed to copy the ELF metadata of the module at {0:#x}")]
+    CopyFromProcessFailed(usize, #[source] DumperError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionDataSegmentsError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+    #[error("Failed to read the data segments of the module at {0:#x}")]
+    ReadSegmentsFailed(usize, #[source] DumperError),
+    #[error("Failed to copy the data segments of the module at {0:#x}")]
+    CopyFromProcessFailed(usize, #[source] DumperError),
+    #[error("Failed to sanitize the data segments")]
+    SanitizeFailed(#[from] DumperError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionMemoryRulesError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+    #[error("Failed to read the process map file")]
+    ReadMapsFailed(
+        #[source]
+        #[serde(serialize_with = "serialize_proc_error")]
+        procfs_core::ProcError,
+    ),
+    #[error("Failed to copy the memory at {0:#x}")]
+    CopyFromProcessFailed(usize, #[source] DumperError),
+    #[error("Failed to sanitize the memory")]
+    SanitizeFailed(#[from] DumperError),
+}
+
+#[derive(Debug, Error, serde::Serialize)]
+pub enum SectionSanitizationStatsError {
+    #[error("Failed to write to memory")]
+    MemoryWriterError(#[from] MemoryWriterError),
+}
+
 #[derive(Debug, Error, serde::Serialize)]
 pub enum SectionSystemInfoError {
     #[error("Failed to write to memory")]
@@ -319,12 +483,32 @@ pub enum WriterError {
     SectionAppMemoryError(#[from] SectionAppMemoryError),
     #[error("Failed when writing section ExceptionStream")]
     SectionExceptionStreamError(#[from] SectionExceptionStreamError),
+    #[error("Failed when writing section DumpReason")]
+    SectionDumpReasonError(#[from] SectionDumpReasonError),
+    #[error("Failed when writing section RustPanic")]
+    SectionRustPanicError(#[from] SectionRustPanicError),
+    #[error("Failed when writing section StackOverflow")]
+    SectionStackOverflowError(#[from] SectionStackOverflowError),
+    #[error("Failed when writing section SignalFrames")]
+    SectionSignalFramesError(#[from] SectionSignalFramesError),
     #[error("Failed when writing section HandleDataStream")]
     SectionHandleDataStreamError(#[from] SectionHandleDataStreamError),
     #[error("Failed when writing section MappingsError")]
     SectionMappingsError(#[from] SectionMappingsError),
     #[error("Failed when writing section MemList")]
     SectionMemListError(#[from] SectionMemListError),
+    #[error("Failed when writing section ModuleInfo")]
+    SectionModuleInfoError(#[from] SectionModuleInfoError),
+    #[error("Failed when writing section JitSymbols")]
+    SectionJitSymbolsError(#[from] SectionJitSymbolsError),
+    #[error("Failed when writing section ModuleMetadata")]
+    SectionModuleMetadataError(#[from] SectionModuleMetadataError),
+    #[error("Failed when writing section DataSegments")]
+    SectionDataSegmentsError(#[from] SectionDataSegmentsError),
+    #[error("Failed when writing section MemoryRules")]
+    SectionMemoryRulesError(#[from] SectionMemoryRulesError),
+    #[error("Failed when writing section SanitizationStats")]
+    SectionSanitizationStatsError(#[from] SectionSanitizationStatsError),
     #[error("Failed when writing section SystemInfo")]
     SectionSystemInfoError(#[from] SectionSystemInfoError),
     #[error("Failed when writing section MemoryInfoList")]
@@ -355,6 +539,14 @@ pub enum WriterError {
     PrincipalMappingNotReferenced,
     #[error("Errors occurred while writing system info")]
     WriteSystemInfoErrors(#[source] ErrorList<SectionSystemInfoError>),
+    #[error("Errors occurred while writing the module list")]
+    WriteMappingsErrors(#[source] ErrorList<SectionMappingsError>),
+    #[error("Errors occurred while capturing module metadata")]
+    WriteModuleMetadataErrors(#[source] ErrorList<SectionModuleMetadataError>),
+    #[error("Errors occurred while capturing data segments")]
+    WriteDataSegmentsErrors(#[source] ErrorList<SectionDataSegmentsError>),
+    #[error("Errors occurred while including the memory of the memory rules")]
+    WriteMemoryRulesErrors(#[source] ErrorList<SectionMemoryRulesError>),
     #[error("Failed writing cpuinfo")]
     WriteCpuInfoFailed(#[source] MemoryWriterError),
     #[error("Failed writing thread proc status")]
@@ -375,6 +567,12 @@ pub enum WriterError {
     WriteLimitsFailed(#[source] MemoryWriterError),
     #[error("Failed writing handle data stream")]
     WriteHandleDataStreamFailed(#[source] SectionHandleDataStreamError),
+    #[error("Failed writing smaps stream")]
+    WriteSmapsStreamFailed(#[source] SectionSmapsError),
+    #[error("Failed writing container info stream")]
+    WriteContainerInfoStreamFailed(#[source] SectionContainerInfoError),
+    #[error("Failed writing security context stream")]
+    WriteSecurityContextStreamFailed(#[source] SectionSecurityContextError),
     #[error("Failed writing handle data stream direction entry")]
     WriteHandleDataStreamDirentFailed(#[source] FileWriterError),
     #[error("No threads left to suspend out of {0}")]
@@ -387,6 +585,38 @@ pub enum WriterError {
     ),
 }
 
+#[derive(Debug, Error, serde::Serialize)]
+pub enum CurrentProcessDumpError {
+    #[error("Failed to create a pipe to the dumping process")]
+    PipeFailed(
+        #[source]
+        #[serde(serialize_with = "serialize_nix_error")]
+        nix::Error,
+    ),
+    #[error("Failed to fork the dumping process")]
+    ForkFailed(
+        #[source]
+        #[serde(serialize_with = "serialize_nix_error")]
+        nix::Error,
+    ),
+    #[error("Failed to allow the dumping process to ptrace this process")]
+    SetPtracerFailed(
+        #[source]
+        #[serde(serialize_with = "serialize_nix_error")]
+        nix::Error,
+    ),
+    #[error("Failed to wait for the dumping process")]
+    WaitFailed(
+        #[source]
+        #[serde(serialize_with = "serialize_nix_error")]
+        nix::Error,
+    ),
+    #[error("The dumping process failed with exit code {0}")]
+    DumpFailed(i32),
+    #[error("The dumping process was killed by a signal")]
+    DumperKilled,
+}
+
 #[derive(Debug, Error, serde::Serialize)]
 pub enum ModuleReaderError {
     #[error("failed to read module file ({path}): {error}")]
@@ -442,6 +672,12 @@ pub enum ModuleReaderError {
     NoSoNameEntry,
     #[error("no dynamic linking information section")]
     NoDynamicSection,
+    #[error("no valid .gnu_debuglink or .gnu_debugaltlink section")]
+    NoDebugLink,
+    #[error("symbol not found in the symbol tables")]
+    NoSymbol,
+    #[error("no dynamic symbol table, or no hash table for it")]
+    NoDynamicSymbols,
     #[error(
         "failed to retrieve soname\n\
     ... from program headers: {program_headers}\n\
@@ -0,0 +1,632 @@
+//! Stream types and raw layouts of the Linux-specific extension streams written
+//! by this crate.
+//!
+//! These streams are not part of the Microsoft or Breakpad minidump formats, so
+//! every layout is documented here. All values are written little-endian, like
+//! the rest of the minidump.
+
+use scroll::{Pread, Pwrite, SizeWith};
+
+/// Extension stream types. 0x4D57 = "MW" (minidump-writer).
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtStreamType {
+    /// Per-mapping memory accounting from `/proc/$pid/smaps`.
+    ///
+    /// A [`MDRawSmapsList`] header followed by `number_of_entries`
+    /// [`MDRawSmapsEntry`] records, one per line of `/proc/$pid/maps`.
+    LinuxSmaps = 0x4d570001,
+    /// Process-wide memory accounting from `/proc/$pid/smaps_rollup`.
+    ///
+    /// Same layout as [`ExtStreamType::LinuxSmaps`], with a single entry
+    /// covering the whole address range that was summarized.
+    LinuxSmapsRollup = 0x4d570002,
+    /// The cgroups, namespaces and container runtime of the process.
+    ///
+    /// A [`MDRawContainerInfo`] header followed by `number_of_cgroups`
+    /// [`MDRawCgroupEntry`] records, one per line of `/proc/$pid/cgroup`.
+    LinuxContainerInfo = 0x4d570003,
+    /// The credentials, capabilities and sandboxing state of the process.
+    ///
+    /// A [`MDRawSecurityContext`] header followed by `number_of_groups`
+    /// supplementary group ids, each a `u32`.
+    LinuxSecurityContext = 0x4d570004,
+    /// Linux specific information about the modules in the module list.
+    ///
+    /// A [`MDRawModuleInfoList`] header followed by `number_of_entries`
+    /// [`MDRawModuleInfo`] records, one per entry of the module list and in
+    /// the same order.
+    LinuxModuleInfo = 0x4d570005,
+    /// The functions in the JIT code modules of the module list.
+    ///
+    /// A [`MDRawJitSymbolList`] header followed by `number_of_entries`
+    /// [`MDRawJitSymbol`] records.
+    LinuxJitSymbols = 0x4d570006,
+    /// What was scrubbed from the memory of the minidump when sanitizing it.
+    ///
+    /// A [`MDRawSanitizationStatsList`] header followed by `number_of_entries`
+    /// [`MDRawSanitizedRegion`] records, one per sanitized region.
+    LinuxSanitizationStats = 0x4d570007,
+    /// Why a dump that wasn't written for a crash was requested.
+    ///
+    /// A [`MDRawDumpReason`] followed by `number_of_parameters` `u64`
+    /// parameters.
+    LinuxDumpReason = 0x4d570008,
+    /// The Rust panic the dump was written for.
+    ///
+    /// A single [`MDRawRustPanic`].
+    LinuxRustPanic = 0x4d570009,
+    /// The threads whose stack overflowed.
+    ///
+    /// A [`MDRawStackOverflowList`] header followed by `number_of_entries`
+    /// [`MDRawStackOverflow`] records.
+    LinuxStackOverflow = 0x4d57000a,
+    /// The threads that were running a signal handler, and whose registers
+    /// and stack in the thread list are the ones the signal interrupted.
+    ///
+    /// A [`MDRawSignalFrameList`] header followed by `number_of_entries`
+    /// [`MDRawSignalFrame`] records.
+    LinuxSignalFrames = 0x4d57000b,
+    /// The alternate signal stack of the crashing thread.
+    ///
+    /// A single [`MDRawSignalStack`].
+    LinuxSignalStack = 0x4d57000c,
+}
+
+/// The exception code of dumps written for a Rust panic, "RUST" in ASCII.
+pub const EXCEPTION_CODE_RUST_PANIC: u32 = 0x52555354;
+
+impl From<ExtStreamType> for u32 {
+    fn from(ty: ExtStreamType) -> Self {
+        ty as u32
+    }
+}
+
+/// Header of the [`ExtStreamType::LinuxSmaps`] and
+/// [`ExtStreamType::LinuxSmapsRollup`] streams.
+#[derive(Debug, Clone, Copy, Default, Pread, Pwrite, SizeWith)]
+pub struct MDRawSmapsList {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The size of each entry in the list
+    pub size_of_entry: u32,
+    /// The number of entries in the list
+    pub number_of_entries: u64,
+}
+
+/// Memory accounting of a single mapping. All sizes are in bytes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawSmapsEntry {
+    /// Start address of the mapping
+    pub base_address: u64,
+    /// Size of the mapping's address range
+    pub region_size: u64,
+    /// `Rss`: memory currently resident in RAM
+    pub rss: u64,
+    /// `Pss`: proportional share of the resident memory
+    pub pss: u64,
+    /// `Private_Dirty`: dirty pages only mapped by this process
+    pub private_dirty: u64,
+    /// `Swap`: anonymous memory that was swapped out
+    pub swap: u64,
+    /// `AnonHugePages`: memory backed by transparent huge pages
+    pub anon_huge_pages: u64,
+    /// `VmFlags`, using the bit assignment of [`procfs_core::process::VmFlags`]
+    /// (`rd` is bit 0, `wr` bit 1, ... in the order listed by proc(5)). Zero
+    /// in the rollup stream, and on kernels older than 3.8.
+    pub vm_flags: u32,
+    pub __alignment: u32,
+}
+
+/// Header of the [`ExtStreamType::LinuxContainerInfo`] stream.
+///
+/// Namespaces are identified by the inode of their `/proc/$pid/ns/*` link, 0
+/// if it couldn't be read. Resource limits are `u64::MAX` if the cgroup is
+/// unlimited and 0 if they couldn't be determined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawContainerInfo {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The size of each entry in the cgroup list
+    pub size_of_cgroup: u32,
+    /// The number of entries in the cgroup list
+    pub number_of_cgroups: u32,
+    /// An RVA to a `MINIDUMP_STRING` with a comma separated list of container
+    /// runtimes the process appears to run in (`docker`, `podman`,
+    /// `containerd`, `kubernetes`, `lxc`, `systemd-nspawn`), 0 if there are
+    /// none
+    pub runtime_hints_rva: u32,
+    /// The pid of the process in its own pid namespace, from `NSpid` in
+    /// `/proc/$pid/status`
+    pub ns_pid: u32,
+    /// The number of nested pid namespaces the process is in, 1 if it is in
+    /// the namespace of the dumper
+    pub pid_ns_depth: u32,
+    pub cgroup_ns: u64,
+    pub ipc_ns: u64,
+    pub mnt_ns: u64,
+    pub net_ns: u64,
+    pub pid_ns: u64,
+    pub time_ns: u64,
+    pub user_ns: u64,
+    pub uts_ns: u64,
+    /// `memory.max` (v2) or `memory.limit_in_bytes` (v1), in bytes
+    pub memory_limit: u64,
+    /// `memory.current` (v2) or `memory.usage_in_bytes` (v1), in bytes
+    pub memory_usage: u64,
+    /// The CPU bandwidth quota per `cpu_period_us`, in microseconds
+    pub cpu_quota_us: u64,
+    /// The CPU bandwidth period, in microseconds
+    pub cpu_period_us: u64,
+    /// The CPU time consumed by the cgroup, in microseconds
+    pub cpu_usage_us: u64,
+}
+
+/// A cgroup the process belongs to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawCgroupEntry {
+    /// The hierarchy ID, 0 for the cgroups v2 hierarchy
+    pub hierarchy: u32,
+    /// An RVA to a `MINIDUMP_STRING` with the comma separated controllers
+    /// bound to the hierarchy, empty for cgroups v2
+    pub controllers_rva: u32,
+    /// An RVA to a `MINIDUMP_STRING` with the path of the cgroup, relative to
+    /// the mount point of the hierarchy
+    pub path_rva: u32,
+    pub __alignment: u32,
+}
+
+/// Header of the [`ExtStreamType::LinuxSecurityContext`] stream, parsed from
+/// `/proc/$pid/status` unless noted otherwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawSecurityContext {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The number of supplementary group ids following the header
+    pub number_of_groups: u32,
+    pub real_uid: u32,
+    pub effective_uid: u32,
+    pub saved_uid: u32,
+    pub filesystem_uid: u32,
+    pub real_gid: u32,
+    pub effective_gid: u32,
+    pub saved_gid: u32,
+    pub filesystem_gid: u32,
+    /// `CapInh`
+    pub cap_inheritable: u64,
+    /// `CapPrm`
+    pub cap_permitted: u64,
+    /// `CapEff`
+    pub cap_effective: u64,
+    /// `CapBnd`, 0 on kernels that don't report it
+    pub cap_bounding: u64,
+    /// `CapAmb`, 0 on kernels that don't report it
+    pub cap_ambient: u64,
+    /// `NoNewPrivs`
+    pub no_new_privs: u32,
+    /// `Seccomp`: 0 if disabled, 1 for strict mode and 2 for filter mode
+    pub seccomp_mode: u32,
+    /// `Seccomp_filters`: the number of seccomp filters attached
+    pub seccomp_filters: u32,
+    /// `/proc/sys/kernel/yama/ptrace_scope`, `u32::MAX` if Yama is not enabled
+    pub ptrace_scope: u32,
+    /// An RVA to a `MINIDUMP_STRING` with the LSM (SELinux, AppArmor...) label
+    /// of the process from `/proc/$pid/attr/current`, 0 if there is none
+    pub lsm_label_rva: u32,
+    pub __alignment: u32,
+}
+
+/// Header of the [`ExtStreamType::LinuxModuleInfo`] stream.
+#[derive(Debug, Clone, Copy, Default, Pread, Pwrite, SizeWith)]
+pub struct MDRawModuleInfoList {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The size of each entry in the list
+    pub size_of_entry: u32,
+    /// The number of entries in the list
+    pub number_of_entries: u64,
+}
+
+/// The file backing the module was deleted or replaced after it was mapped.
+pub const MODULE_FLAG_FILE_DELETED: u32 = 1 << 0;
+/// The module was mapped from a `memfd_create(2)` file.
+pub const MODULE_FLAG_MEMFD: u32 = 1 << 1;
+/// The module was found in anonymous memory. It has no file, and its name is
+/// its SONAME, or `anonymous-elf-<build id>` if it has none.
+pub const MODULE_FLAG_ANONYMOUS: u32 = 1 << 2;
+/// The module is in the dynamic linker's `link_map`, `load_bias` is its
+/// `l_addr`. Only set when the module list is built from the `link_map`.
+pub const MODULE_FLAG_LINK_MAP: u32 = 1 << 3;
+/// The module was loaded through `LD_PRELOAD` or `/etc/ld.so.preload`. Only
+/// set when the module list is built from the `link_map`.
+pub const MODULE_FLAG_PRELOADED: u32 = 1 << 4;
+/// The module has no build id, it can only be identified by its file.
+pub const MODULE_FLAG_NO_BUILD_ID: u32 = 1 << 5;
+/// `file_hash` holds the hash of the module file.
+pub const MODULE_FLAG_FILE_HASH: u32 = 1 << 6;
+/// The module is a region of JIT code. It has no file nor build id, and its
+/// name is `jit-code-<start address>`, or the one it was registered with.
+pub const MODULE_FLAG_JIT: u32 = 1 << 7;
+
+/// Linux specific information about a module.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawModuleInfo {
+    /// `base_of_image` of the module this entry belongs to
+    pub base_of_image: u64,
+    /// A combination of the `MODULE_FLAG_*` values
+    pub flags: u32,
+    pub __alignment: u32,
+    /// The difference between the addresses in the ELF file and in memory, 0
+    /// without [`MODULE_FLAG_LINK_MAP`]
+    pub load_bias: u64,
+    /// Major number of the device of the module file, from `/proc/$pid/maps`
+    pub device_major: u32,
+    /// Minor number of the device of the module file, from `/proc/$pid/maps`
+    pub device_minor: u32,
+    /// Inode of the module file, from `/proc/$pid/maps`
+    pub inode: u64,
+    /// Size of the module file, 0 if it couldn't be read
+    pub file_size: u64,
+    /// Last modification time of the module file, in seconds since the epoch
+    pub file_mtime: i64,
+    /// 64 bit FNV-1a hash of the module file contents, 0 without
+    /// [`MODULE_FLAG_FILE_HASH`]
+    pub file_hash: u64,
+    /// An RVA to a `MINIDUMP_STRING` with the file name in `.gnu_debuglink`,
+    /// 0 if the module has none
+    pub debuglink_rva: u32,
+    /// The CRC32 of the debug file, from `.gnu_debuglink`
+    pub debuglink_crc: u32,
+    /// An RVA to a `MINIDUMP_STRING` with the file name in
+    /// `.gnu_debugaltlink`, 0 if the module has none
+    pub debugaltlink_rva: u32,
+    /// An RVA to a `MINIDUMP_STRING` with the build id of the
+    /// `.gnu_debugaltlink` file, in hex
+    pub debugaltlink_build_id_rva: u32,
+}
+
+/// Header of the [`ExtStreamType::LinuxJitSymbols`] stream.
+#[derive(Debug, Clone, Copy, Default, Pread, Pwrite, SizeWith)]
+pub struct MDRawJitSymbolList {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The size of each entry in the list
+    pub size_of_entry: u32,
+    /// The number of entries in the list
+    pub number_of_entries: u64,
+}
+
+/// A function in JIT code, from the perf map, the GDB JIT interface, or
+/// registered by the caller.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawJitSymbol {
+    /// Start address of the function
+    pub address: u64,
+    /// Size of the function, 0 if unknown
+    pub size: u64,
+    /// An RVA to a `MINIDUMP_STRING` with the name of the function
+    pub name_rva: u32,
+    pub __alignment: u32,
+}
+
+/// Header of the [`ExtStreamType::LinuxSanitizationStats`] stream.
+#[derive(Debug, Clone, Copy, Default, Pread, Pwrite, SizeWith)]
+pub struct MDRawSanitizationStatsList {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The size of each entry in the list
+    pub size_of_entry: u32,
+    /// The number of entries in the list
+    pub number_of_entries: u64,
+}
+
+/// The kind of memory a [`MDRawSanitizedRegion`] describes.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizedRegionKind {
+    /// The stack of a thread
+    Stack = 1,
+    /// A region of application provided memory
+    AppMemory = 2,
+    /// The memory around the instruction pointer of the crashing thread
+    InstructionPointer = 3,
+    /// A window of memory a register points to
+    RegisterMemory = 4,
+    /// The memory around the thread pointer of a thread
+    ThreadPointer = 5,
+    /// The writable segments of a module
+    DataSegment = 6,
+}
+
+/// What was scrubbed from a region of the memory list. Words are
+/// pointer-sized.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawSanitizedRegion {
+    /// Start address of the region
+    pub start_of_memory_range: u64,
+    /// Size of the region
+    pub data_size: u64,
+    /// A [`SanitizedRegionKind`]
+    pub kind: u32,
+    /// 0 if scrubbed words were overwritten with `0x0defaced`, 1 if with
+    /// zeroes
+    pub scrub_mode: u32,
+    /// Words kept because they are small integers
+    pub kept_small_ints: u64,
+    /// Words kept because they are pointers to memory that is kept
+    pub kept_pointers: u64,
+    /// Words that were scrubbed
+    pub scrubbed_words: u64,
+    /// Bytes below the stack pointer, or in a partial word, that were zeroed
+    pub zeroed_bytes: u64,
+}
+
+/// A `MINIDUMP_HANDLE_DESCRIPTOR_2`. minidump-common doesn't allow
+/// constructing its own definition outside of the crate, so this mirrors it.
+#[derive(Debug, Clone, Copy, Default, Pread, Pwrite, SizeWith)]
+pub struct MDRawHandleDescriptor2 {
+    /// The file descriptor number
+    pub handle: u64,
+    /// An RVA to a `MINIDUMP_STRING` with the kind of file descriptor, see
+    /// [`HANDLE_TYPE_NAMES`]
+    pub type_name_rva: u32,
+    /// An RVA to a `MINIDUMP_STRING` with the target of `/proc/$pid/fd/$fd`
+    pub object_name_rva: u32,
+    /// `st_mode` of the file the descriptor refers to
+    pub attributes: u32,
+    pub granted_access: u32,
+    pub handle_count: u32,
+    pub pointer_count: u32,
+    /// An RVA to the first `MINIDUMP_HANDLE_OBJECT_INFORMATION` of the
+    /// descriptor, 0 if there is none
+    pub object_info_rva: u32,
+    pub reserved0: u32,
+}
+
+/// The values used as type names of the entries in the handle data stream.
+pub const HANDLE_TYPE_NAMES: &[&str] = &[
+    "file",
+    "dir",
+    "device",
+    "pipe",
+    "socket",
+    "eventfd",
+    "timerfd",
+    "signalfd",
+    "inotify",
+    "memfd",
+    "anon_inode",
+];
+
+/// Linux has no `MINIDUMP_HANDLE_OBJECT_INFORMATION_TYPE` of its own, so all
+/// object information is written as `MiniHandleObjectInformationNone`, and the
+/// data following the `MINIDUMP_HANDLE_OBJECT_INFORMATION` header starts with
+/// one of these tags.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HandleObjectInfoKind {
+    /// Followed by the rest of a [`MDRawFdInfo`]
+    FdInfo = 1,
+    /// Followed by the rest of a [`MDRawSocketInfo`]
+    Socket = 2,
+}
+
+/// Information about a file descriptor from `/proc/$pid/fdinfo/$fd`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawFdInfo {
+    /// [`HandleObjectInfoKind::FdInfo`]
+    pub kind: u32,
+    /// The `open(2)` flags the descriptor was opened with
+    pub flags: u32,
+    /// The file offset
+    pub pos: u64,
+    /// The id of the mount the file resides on, see `/proc/$pid/mountinfo`
+    pub mnt_id: u32,
+    pub __alignment: u32,
+}
+
+/// Information about a socket, resolved through the tables in
+/// `/proc/$pid/net`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawSocketInfo {
+    /// [`HandleObjectInfoKind::Socket`]
+    pub kind: u32,
+    /// `AF_INET`, `AF_INET6` or `AF_UNIX`
+    pub family: u16,
+    /// `SOCK_STREAM`, `SOCK_DGRAM` or `SOCK_SEQPACKET`
+    pub socket_type: u16,
+    /// The inode of the socket
+    pub inode: u64,
+    /// The kernel's state of the socket, `TCP_ESTABLISHED`, `TCP_LISTEN`...
+    /// for internet sockets and `SS_UNCONNECTED`, `SS_CONNECTED`... for unix
+    /// sockets.
+    pub state: u32,
+    /// The local port, in host byte order
+    pub local_port: u16,
+    /// The remote port, in host byte order
+    pub remote_port: u16,
+    /// The local address, in network byte order. Only the first 4 bytes are
+    /// used for `AF_INET`.
+    pub local_address: [u8; 16],
+    /// The remote address, in network byte order. Only the first 4 bytes are
+    /// used for `AF_INET`.
+    pub remote_address: [u8; 16],
+    /// For `AF_UNIX`, an RVA to a `MINIDUMP_STRING` with the path the socket
+    /// is bound to (`@` prefixed for the abstract namespace), or 0.
+    pub path_rva: u32,
+    pub __alignment: u32,
+}
+
+/// Header of the [`ExtStreamType::LinuxStackOverflow`] stream.
+#[derive(Debug, Clone, Copy, Default, Pread, Pwrite, SizeWith)]
+pub struct MDRawStackOverflowList {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The size of each entry in the list
+    pub size_of_entry: u32,
+    /// The number of entries in the list
+    pub number_of_entries: u64,
+}
+
+/// The stack pointer of the thread is in the guard of its stack.
+pub const STACK_OVERFLOW_FLAG_STACK_POINTER: u32 = 1 << 0;
+/// The address of the `SIGSEGV` the thread crashed with is in the guard of
+/// its stack.
+pub const STACK_OVERFLOW_FLAG_FAULT_ADDRESS: u32 = 1 << 1;
+
+/// A thread whose stack overflowed into the guard below it. The deepest part
+/// of the stack, right above the guard, is in the memory list even if the
+/// stack of the thread was truncated or left out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawStackOverflow {
+    /// The id of the thread
+    pub thread_id: u32,
+    /// `STACK_OVERFLOW_FLAG_*`
+    pub flags: u32,
+    /// The stack pointer of the thread
+    pub stack_pointer: u64,
+    /// The address of the `SIGSEGV`, 0 if the thread didn't crash with one
+    pub fault_address: u64,
+    /// Start of the inaccessible mapping or the unmapped gap below the stack
+    pub guard_start: u64,
+    /// End of the guard, and start of the stack mapping
+    pub guard_end: u64,
+}
+
+/// Header of the [`ExtStreamType::LinuxSignalFrames`] stream.
+#[derive(Debug, Clone, Copy, Default, Pread, Pwrite, SizeWith)]
+pub struct MDRawSignalFrameList {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The size of each entry in the list
+    pub size_of_entry: u32,
+    /// The number of entries in the list
+    pub number_of_entries: u64,
+}
+
+/// A thread running a signal handler. The stack of the handler, up to the
+/// end of the signal frame, is in the memory list if the stack of the thread
+/// doesn't cover it, e.g. when the handler runs on the alternate signal stack.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawSignalFrame {
+    /// The id of the thread
+    pub thread_id: u32,
+    /// The signal the handler was called for, 0 if the handler wasn't
+    /// installed with `SA_SIGINFO`
+    pub signal: u32,
+    /// The address of the `rt_sigframe` on the stack of the handler
+    pub frame_address: u64,
+    /// The stack pointer of the thread in the handler
+    pub handler_stack_pointer: u64,
+    /// The instruction pointer of the thread in the handler
+    pub handler_instruction_pointer: u64,
+}
+
+/// The [`ExtStreamType::LinuxSignalStack`] stream. The crash context only
+/// covers the stack the signal interrupted, so the stack of the handler,
+/// which ran on the alternate signal stack, is written as an extra block of
+/// the memory list.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawSignalStack {
+    /// The id of the crashing thread
+    pub thread_id: u32,
+    /// `ss_flags` of the alternate signal stack, `SS_ONSTACK` if the crash
+    /// happened on it
+    pub flags: u32,
+    /// Start address of the alternate signal stack
+    pub ss_sp: u64,
+    /// Size of the alternate signal stack
+    pub ss_size: u64,
+    /// The stack pointer of the thread in the handler
+    pub handler_stack_pointer: u64,
+    /// Start address of the memory block with the stack of the handler
+    pub start_of_memory_range: u64,
+    /// Size of the memory block, 0 if the stack of the handler is already
+    /// covered by the stack of the thread, couldn't be read, or doesn't fit
+    /// within the minidump size limit
+    pub data_size: u32,
+    /// RVA of the memory block
+    pub rva: u32,
+}
+
+/// The [`ExtStreamType::LinuxDumpReason`] stream. The exception code and the
+/// first 15 parameters are also written to the exception stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawDumpReason {
+    /// The size of this header
+    pub size_of_header: u32,
+    /// The exception code set by the caller
+    pub exception_code: u32,
+    /// An RVA to a `MINIDUMP_STRING` with the reason, 0 if there is none
+    pub reason_rva: u32,
+    pub __alignment: u32,
+    /// The number of parameters following this header
+    pub number_of_parameters: u64,
+}
+
+/// The [`ExtStreamType::LinuxRustPanic`] stream. Strings are RVAs to a
+/// `MINIDUMP_STRING`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Pread, Pwrite, SizeWith)]
+pub struct MDRawRustPanic {
+    /// The id of the panicking thread
+    pub thread_id: u32,
+    /// The line of the panic in `file`
+    pub line: u32,
+    /// The column of the panic in `file`
+    pub column: u32,
+    /// The panic message
+    pub message_rva: u32,
+    /// The source file the panic originated in
+    pub file_rva: u32,
+    /// The name of the panicking thread, 0 if it has none
+    pub thread_name_rva: u32,
+    /// The backtrace of the panicking thread, as rendered by
+    /// `std::backtrace::Backtrace`
+    pub backtrace_rva: u32,
+    pub __alignment: u32,
+}
+
+/// Indices of the `siginfo` fields in `exception_information` of the exception
+/// stream, when the dump was written for a crash. `ssi_signo`, `ssi_code` and
+/// `ssi_addr` are the exception code, flags and address. Signed fields are
+/// sign-extended, and which fields are meaningful depends on the signal and
+/// `ssi_code`, see `sigaction(2)`. `ssi_utime` and `ssi_stime` of `SIGCHLD`
+/// are left out, as there are only 15 slots. The bounds and protection key
+/// of `SEGV_BNDERR` and `SEGV_PKUERR` aren't part of `signalfd_siginfo`, so
+/// they aren't available.
+#[repr(usize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SiginfoIndex {
+    /// `ssi_errno`, usually 0
+    Errno = 0,
+    /// `ssi_pid`, the process that sent the signal, for `kill(2)`,
+    /// `sigqueue(3)` and `SIGCHLD`
+    Pid = 1,
+    /// `ssi_uid`, the real user id of the process that sent the signal
+    Uid = 2,
+    /// `ssi_fd`, the file descriptor of `SIGIO`
+    Fd = 3,
+    /// `ssi_tid`, the kernel timer id of POSIX timers
+    Tid = 4,
+    /// `ssi_band`, the band event of `SIGIO`
+    Band = 5,
+    /// `ssi_overrun`, the overrun count of POSIX timers
+    Overrun = 6,
+    /// `ssi_trapno`, the trap number that caused a hardware signal
+    Trapno = 7,
+    /// `ssi_status`, the exit status or signal of `SIGCHLD`
+    Status = 8,
+    /// `ssi_int`, the integer sent by `sigqueue(3)`
+    Int = 9,
+    /// `ssi_ptr`, the pointer sent by `sigqueue(3)`
+    Ptr = 10,
+    /// `ssi_addr_lsb`, the least significant bit of the address of
+    /// `BUS_MCEERR_*` and `SEGV_*` signals
+    AddrLsb = 11,
+    /// `ssi_syscall`, the number of the system call of `SIGSYS`
+    Syscall = 12,
+    /// `ssi_call_addr`, the address of the system call instruction of `SIGSYS`
+    CallAddr = 13,
+    /// `ssi_arch`, the `AUDIT_ARCH_*` of the system call of `SIGSYS`
+    Arch = 14,
+}
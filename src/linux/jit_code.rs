@@ -0,0 +1,383 @@
+//! Code generated at runtime by JIT compilers, which isn't part of any module.
+//!
+//! Regions of JIT code can be registered by the caller, or read from the
+//! process: from the perf map JITs write for `perf(1)`, and from the GDB JIT
+//! interface. Every region is written as a synthetic module, and the symbols
+//! in it to a separate stream, so that processors can label JIT frames.
+
+use crate::{
+    errors::JitCodeError,
+    maps_reader::MappingInfo,
+    module_reader::{ModuleReader, ProcessReader},
+    ptrace_dumper::PtraceDumper,
+    Pid,
+};
+use error_graph::WriteErrorList;
+use goblin::elf;
+
+/// The symbol of the GDB JIT interface descriptor.
+const JIT_DESCRIPTOR_SYMBOL: &str = "__jit_debug_descriptor";
+/// The only version of the GDB JIT interface.
+const JIT_DESCRIPTOR_VERSION: u32 = 1;
+/// Upper bound of the number of registered objects, in case the list is corrupt.
+const MAX_JIT_ENTRIES: usize = 4096;
+/// Upper bound of the size of a registered object.
+const MAX_JIT_OBJECT_SIZE: usize = 16 * 1024 * 1024;
+
+/// A function in a region of JIT code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JitSymbol {
+    pub address: usize,
+    pub size: usize,
+    pub name: String,
+}
+
+/// A region of JIT code, written as a module named `name`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JitCodeRegion {
+    pub start_address: usize,
+    pub size: usize,
+    pub name: String,
+    pub symbols: Vec<JitSymbol>,
+}
+
+pub type JitCodeList = Vec<JitCodeRegion>;
+
+/// `struct jit_descriptor` of the GDB JIT interface.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct JitDescriptor {
+    version: u32,
+    action_flag: u32,
+    relevant_entry: usize,
+    first_entry: usize,
+}
+
+/// `struct jit_code_entry` of the GDB JIT interface.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct JitCodeEntry {
+    next_entry: usize,
+    prev_entry: usize,
+    symfile_addr: usize,
+    symfile_size: u64,
+}
+
+/// The name of the module of JIT code without a better name.
+fn region_name(start_address: usize) -> String {
+    format!("jit-code-{start_address:x}")
+}
+
+/// Read the symbols from `/tmp/perf-<pid>.map` in the mount namespace of the
+/// process, where `<pid>` is `ns_pid`, the pid of the process in its own pid
+/// namespace. Symbols are grouped into one region per mapping they are in.
+/// Symbols in file backed mappings are left out, as those are modules already.
+pub(crate) fn read_perf_map(
+    pid: Pid,
+    ns_pid: Pid,
+    mappings: &[MappingInfo],
+) -> Result<JitCodeList, JitCodeError> {
+    let path = format!("/proc/{pid}/root/tmp/perf-{ns_pid}.map");
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(JitCodeList::new()),
+        Err(e) => return Err(JitCodeError::ReadPerfMapFailed(path, e)),
+    };
+
+    let mut regions = JitCodeList::new();
+    for symbol in parse_perf_map(&contents) {
+        let Some(mapping) = mappings
+            .iter()
+            .find(|mapping| mapping.contains_address(symbol.address))
+        else {
+            continue;
+        };
+        if mapping.name_is_path() && !mapping.is_memfd() {
+            continue;
+        }
+        match regions
+            .iter_mut()
+            .find(|region| region.start_address == mapping.start_address)
+        {
+            Some(region) => region.symbols.push(symbol),
+            None => regions.push(JitCodeRegion {
+                start_address: mapping.start_address,
+                size: mapping.size,
+                name: region_name(mapping.start_address),
+                symbols: vec![symbol],
+            }),
+        }
+    }
+    Ok(regions)
+}
+
+/// Every line of a perf map is `START SIZE symbol`, with hexadecimal numbers.
+/// Malformed lines are skipped.
+fn parse_perf_map(contents: &str) -> Vec<JitSymbol> {
+    fn parse_hex(s: &str) -> Option<usize> {
+        usize::from_str_radix(s.strip_prefix("0x").unwrap_or(s), 16).ok()
+    }
+
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.trim().splitn(3, ' ');
+            let address = parse_hex(fields.next()?)?;
+            let size = parse_hex(fields.next()?)?;
+            let name = fields.next()?.trim();
+            Some(JitSymbol {
+                address,
+                size,
+                name: name.to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// Read the objects registered with the GDB JIT interface, one region per
+/// object. Objects that can't be read are reported as soft errors.
+pub(crate) fn read_gdb_jit(
+    pid: Pid,
+    mappings: &[MappingInfo],
+    mut soft_errors: impl WriteErrorList<JitCodeError>,
+) -> Result<JitCodeList, JitCodeError> {
+    let Some(descriptor_address) = find_jit_descriptor(pid, mappings) else {
+        return Ok(JitCodeList::new());
+    };
+    let descriptor: JitDescriptor = copy_struct_from_process(pid, descriptor_address)
+        .map_err(|e| JitCodeError::ReadDescriptorFailed(descriptor_address, e))?;
+    if descriptor.version != JIT_DESCRIPTOR_VERSION {
+        return Err(JitCodeError::UnsupportedDescriptorVersion(
+            descriptor.version,
+        ));
+    }
+
+    let mut regions = JitCodeList::new();
+    let mut entry_address = descriptor.first_entry;
+    for _ in 0..MAX_JIT_ENTRIES {
+        if entry_address == 0 {
+            break;
+        }
+        let entry: JitCodeEntry = match copy_struct_from_process(pid, entry_address) {
+            Ok(entry) => entry,
+            Err(e) => {
+                soft_errors.push(JitCodeError::ReadEntryFailed(entry_address, e));
+                break;
+            }
+        };
+        match read_jit_object(
+            pid,
+            entry.symfile_addr,
+            entry.symfile_size as usize,
+            &mut soft_errors,
+        ) {
+            Ok(Some(region)) => regions.push(region),
+            Ok(None) => {}
+            Err(e) => soft_errors.push(e),
+        }
+        entry_address = entry.next_entry;
+    }
+    Ok(regions)
+}
+
+/// Find the address of `__jit_debug_descriptor` among the symbols exported by
+/// the modules, starting with the main executable, which is the first mapping.
+fn find_jit_descriptor(pid: Pid, mappings: &[MappingInfo]) -> Option<usize> {
+    mappings
+        .iter()
+        .filter(|mapping| mapping.offset == 0 && mapping.name_is_path())
+        .find_map(|mapping| {
+            let offset = ModuleReader::new(ProcessReader::new(pid, mapping.start_address).into())
+                .and_then(|mut reader| reader.exported_symbol_offset(JIT_DESCRIPTOR_SYMBOL))
+                .ok()?;
+            log::debug!("found {JIT_DESCRIPTOR_SYMBOL} in {:?}", mapping.name);
+            Some(mapping.start_address + offset as usize)
+        })
+}
+
+/// Read a JIT object, an ELF file with the JIT code at its final address.
+fn read_jit_object(
+    pid: Pid,
+    address: usize,
+    size: usize,
+    soft_errors: &mut impl WriteErrorList<JitCodeError>,
+) -> Result<Option<JitCodeRegion>, JitCodeError> {
+    let data = PtraceDumper::copy_from_process(pid, address, size.min(MAX_JIT_OBJECT_SIZE))
+        .map_err(|e| JitCodeError::ReadObjectFailed(address, e))?;
+    parse_jit_object(&data, address, soft_errors)
+}
+
+/// The region of a JIT object spans the executable sections, and the functions
+/// if there are none. Sections and functions whose range doesn't fit in the
+/// address space are left out, and reported as soft errors.
+fn parse_jit_object(
+    data: &[u8],
+    address: usize,
+    soft_errors: &mut impl WriteErrorList<JitCodeError>,
+) -> Result<Option<JitCodeRegion>, JitCodeError> {
+    let object = elf::Elf::parse(data).map_err(|e| JitCodeError::ParseObjectFailed(address, e))?;
+
+    let mut symbols = Vec::new();
+    let mut symbol_bounds = Vec::new();
+    for sym in object
+        .syms
+        .iter()
+        .filter(|sym| sym.st_type() == elf::sym::STT_FUNC && sym.st_value != 0)
+    {
+        let Some(end) = range_end(sym.st_value, sym.st_size.max(1)) else {
+            soft_errors.push(JitCodeError::RangeOverflow(
+                address,
+                sym.st_value,
+                sym.st_size,
+            ));
+            continue;
+        };
+        symbol_bounds.push((sym.st_value as usize, end));
+        symbols.push(JitSymbol {
+            address: sym.st_value as usize,
+            size: sym.st_size as usize,
+            name: object
+                .strtab
+                .get_at(sym.st_name)
+                .unwrap_or_default()
+                .to_owned(),
+        });
+    }
+
+    let mut code = Vec::new();
+    for header in object.section_headers.iter().filter(|header| {
+        header.sh_flags & u64::from(elf::section_header::SHF_EXECINSTR) != 0
+            && header.sh_addr != 0
+            && header.sh_size != 0
+    }) {
+        let Some(end) = range_end(header.sh_addr, header.sh_size) else {
+            soft_errors.push(JitCodeError::RangeOverflow(
+                address,
+                header.sh_addr,
+                header.sh_size,
+            ));
+            continue;
+        };
+        code.push((header.sh_addr as usize, end));
+    }
+    let bounds = if code.is_empty() { symbol_bounds } else { code };
+    let (Some(start_address), Some(end_address)) = (
+        bounds.iter().map(|(start, _)| *start).min(),
+        bounds.iter().map(|(_, end)| *end).max(),
+    ) else {
+        return Ok(None);
+    };
+
+    Ok(Some(JitCodeRegion {
+        start_address,
+        size: end_address - start_address,
+        name: region_name(start_address),
+        symbols,
+    }))
+}
+
+/// The end of the `size` bytes at `start`, if they are in the address space.
+fn range_end(start: u64, size: u64) -> Option<usize> {
+    start
+        .checked_add(size)
+        .and_then(|end| usize::try_from(end).ok())
+}
+
+/// Copy a plain `repr(C)` struct, for which any bit pattern is valid, from the
+/// process.
+fn copy_struct_from_process<T: Copy>(
+    pid: Pid,
+    address: usize,
+) -> Result<T, crate::errors::DumperError> {
+    let data = PtraceDumper::copy_from_process(pid, address, std::mem::size_of::<T>())?;
+    // Safety: `copy_from_process` returns exactly `size_of::<T>()` bytes
+    Ok(unsafe { std::ptr::read_unaligned(data.as_ptr().cast()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A little endian ELF64 object with a `SHT_NOBITS` code section at each
+    /// of the `(address, size)` pairs in `code`.
+    fn jit_object(code: &[(u64, u64)]) -> Vec<u8> {
+        let mut object = vec![0u8; 64];
+        object[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        object[16..18].copy_from_slice(&1u16.to_le_bytes()); // ET_REL
+        object[20..24].copy_from_slice(&1u32.to_le_bytes()); // EV_CURRENT
+        object[40..48].copy_from_slice(&64u64.to_le_bytes()); // section headers
+        object[52..54].copy_from_slice(&64u16.to_le_bytes()); // ELF header size
+        object[58..60].copy_from_slice(&64u16.to_le_bytes()); // section header size
+        object[60..62].copy_from_slice(&(code.len() as u16 + 1).to_le_bytes());
+
+        object.extend_from_slice(&[0; 64]);
+        for (address, size) in code {
+            let flags =
+                u64::from(elf::section_header::SHF_ALLOC | elf::section_header::SHF_EXECINSTR);
+            object.extend_from_slice(&0u32.to_le_bytes()); // name
+            object.extend_from_slice(&elf::section_header::SHT_NOBITS.to_le_bytes());
+            object.extend_from_slice(&flags.to_le_bytes());
+            object.extend_from_slice(&address.to_le_bytes());
+            object.extend_from_slice(&0u64.to_le_bytes()); // offset
+            object.extend_from_slice(&size.to_le_bytes());
+            object.extend_from_slice(&[0; 8]); // link, info
+            object.extend_from_slice(&16u64.to_le_bytes()); // alignment
+            object.extend_from_slice(&0u64.to_le_bytes()); // entry size
+        }
+        object
+    }
+
+    #[test]
+    fn test_parse_jit_object() {
+        let mut soft_errors = error_graph::ErrorList::default();
+        let region = parse_jit_object(
+            &jit_object(&[(0x1000, 0x100), (0x2000, 0x10)]),
+            0x7f00,
+            &mut soft_errors,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!((region.start_address, region.size), (0x1000, 0x1010));
+        assert!(soft_errors.is_empty());
+
+        // A section overflowing the address space is left out
+        let region = parse_jit_object(
+            &jit_object(&[(u64::MAX - 0x8, 0x10), (0x2000, 0x10)]),
+            0x7f00,
+            &mut soft_errors,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!((region.start_address, region.size), (0x2000, 0x10));
+        assert!(matches!(
+            soft_errors.iter().collect::<Vec<_>>()[..],
+            [JitCodeError::RangeOverflow(0x7f00, _, 0x10)]
+        ));
+    }
+
+    #[test]
+    fn test_parse_perf_map() {
+        let symbols = parse_perf_map(
+            "7f001000 40 LazyCompile:~foo bar.js:1\n\
+             0x7f001040 0x8 stub\n\
+             \n\
+             not a symbol\n\
+             7f001048 10\n",
+        );
+        assert_eq!(
+            symbols,
+            [
+                JitSymbol {
+                    address: 0x7f001000,
+                    size: 0x40,
+                    name: "LazyCompile:~foo bar.js:1".into(),
+                },
+                JitSymbol {
+                    address: 0x7f001040,
+                    size: 0x8,
+                    name: "stub".into(),
+                },
+            ]
+        );
+    }
+}
@@ -1,5 +1,5 @@
 use {
-    crate::{auxv::AuxvType, errors::MapsReaderError},
+    crate::{auxv::AuxvType, errors::MapsReaderError, Pid},
     byteorder::{NativeEndian, ReadBytesExt},
     goblin::elf,
     memmap2::{Mmap, MmapOptions},
@@ -9,7 +9,7 @@ use {
         fs::File,
         mem::size_of,
         os::unix::ffi::{OsStrExt, OsStringExt},
-        path::PathBuf,
+        path::{Path, PathBuf},
     },
 };
 
@@ -43,6 +43,15 @@ pub struct MappingInfo {
     pub offset: usize,              // offset into the backed file.
     pub permissions: MMPermissions, // read, write and execute permissions.
     pub name: Option<OsString>,
+    // Whether the backing file was deleted (or replaced) after it was mapped.
+    pub deleted: bool,
+    // Major and minor number of the device of the backing file, and its inode.
+    pub device: (u32, u32),
+    pub inode: u64,
+    // The end of the first mapping in this aggregate, which together with
+    // |system_mapping_info.start_address| names its entry in
+    // /proc/$pid/map_files. 0 if this isn't a mapping of the process.
+    pub first_mapping_end: usize,
     // pub elf_obj: Option<elf::Elf>,
 }
 
@@ -104,19 +113,8 @@ impl MappingInfo {
             let end_address: usize = mm.address.1.try_into()?;
             let mut offset: usize = mm.offset.try_into()?;
 
-            let mut pathname: Option<OsString> = match mm.pathname {
-                MMapPath::Path(p) => Some(sanitize_path(p.into())),
-                MMapPath::Heap => Some("[heap]".into()),
-                MMapPath::Stack => Some("[stack]".into()),
-                MMapPath::TStack(i) => Some(format!("[stack:{i}]").into()),
-                MMapPath::Vdso => Some("[vdso]".into()),
-                MMapPath::Vvar => Some("[vvar]".into()),
-                MMapPath::Vsyscall => Some("[vsyscall]".into()),
-                MMapPath::Rollup => Some("[rollup]".into()),
-                MMapPath::Vsys(i) => Some(format!("/SYSV{i:x}").into()),
-                MMapPath::Other(n) => Some(format!("[{n}]").into()),
-                MMapPath::Anonymous => None,
-            };
+            let deleted = matches!(&mm.pathname, MMapPath::Path(p) if p.as_os_str().as_bytes().ends_with(DELETED_SUFFIX));
+            let mut pathname = mapping_name(&mm.pathname);
 
             let is_path = is_mapping_a_path(pathname.as_deref());
 
@@ -190,25 +188,77 @@ impl MappingInfo {
                 offset,
                 permissions: mm.perms,
                 name: pathname,
+                deleted,
+                device: (mm.dev.0 as u32, mm.dev.1 as u32),
+                inode: mm.inode,
+                first_mapping_end: end_address,
             });
         }
         Ok(infos)
     }
 
-    pub fn get_mmap(name: &Option<OsString>, offset: usize) -> Result<Mmap> {
-        if !MappingInfo::is_mapped_file_safe_to_open(name) {
+    /// Whether the mapping is backed by a `memfd_create(2)` file.
+    pub fn is_memfd(&self) -> bool {
+        self.name
+            .as_deref()
+            .is_some_and(|name| name.as_bytes().starts_with(b"/memfd:"))
+    }
+
+    /// The paths the file backing this mapping can be opened with, in order
+    /// of preference.
+    ///
+    /// The name in `/proc/$pid/maps` is relative to the mount namespace of the
+    /// process, which is not ours if it runs in a container. The entry of the
+    /// mapping in `/proc/$pid/map_files` refers to the mapped file itself, even
+    /// if it was deleted or replaced, but opening it requires `CAP_SYS_ADMIN`,
+    /// so the name is also resolved below `/proc/$pid/root`.
+    pub fn file_paths(&self, pid: Pid) -> Vec<PathBuf> {
+        let Some(name) = self.name.as_deref().map(Path::new) else {
+            return Vec::new();
+        };
+        let Ok(relative_name) = name.strip_prefix("/") else {
+            return Vec::new();
+        };
+
+        let mut paths = Vec::with_capacity(2);
+        // Entries are named after the address range of the single mapping they
+        // refer to, which may be smaller than the range of this aggregate.
+        if self.first_mapping_end != 0 {
+            paths.push(PathBuf::from(format!(
+                "/proc/{pid}/map_files/{:x}-{:x}",
+                self.system_mapping_info.start_address, self.first_mapping_end
+            )));
+        }
+        // If the file was deleted, a file at the same path is a different one
+        if !self.deleted {
+            paths.push(Path::new(&format!("/proc/{pid}/root")).join(relative_name));
+        }
+        paths
+    }
+
+    /// Open the file backing this mapping, see [`MappingInfo::file_paths`].
+    pub fn open_file(&self, pid: Pid) -> Result<File> {
+        let mut error = std::io::Error::from(std::io::ErrorKind::NotFound);
+        for path in self.file_paths(pid) {
+            match File::open(path) {
+                Ok(file) => return Ok(file),
+                Err(e) => error = e,
+            }
+        }
+        Err(error.into())
+    }
+
+    pub fn get_mmap(&self, pid: Pid) -> Result<Mmap> {
+        if !MappingInfo::is_mapped_file_safe_to_open(&self.name) {
             return Err(MapsReaderError::NotSafeToOpenMapping(
-                name.clone().unwrap_or_default(),
+                self.name.clone().unwrap_or_default(),
             ));
         }
 
-        // Not doing this as root_prefix is always "" at the moment
-        //   if (!dumper.GetMappingAbsolutePath(mapping, filename))
-        let filename = name.clone().unwrap_or_default();
         let mapped_file = unsafe {
             MmapOptions::new()
-                .offset(offset.try_into()?) // try_into() to work for both 32 and 64 bit
-                .map(&File::open(filename)?)?
+                .offset(self.offset.try_into()?) // try_into() to work for both 32 and 64 bit
+                .map(&self.open_file(pid)?)?
         };
 
         if mapped_file.is_empty() || mapped_file.len() < elf::header::SELFMAG {
@@ -265,10 +315,10 @@ impl MappingInfo {
 
     /// Find the shared object name (SONAME) by examining the ELF information
     /// for the mapping.
-    fn so_name(&self) -> Result<String> {
+    fn so_name(&self, pid: Pid) -> Result<String> {
         use super::module_reader::{ReadFromModule, SoName};
 
-        let mapped_file = MappingInfo::get_mmap(&self.name, self.offset)?;
+        let mapped_file = self.get_mmap(pid)?;
         Ok(SoName::read_from_module((&*mapped_file).into())
             .map_err(|e| MapsReaderError::NoSoName(self.name.clone().unwrap_or_default(), e))?
             .0
@@ -282,6 +332,7 @@ impl MappingInfo {
 
     pub fn get_mapping_effective_path_name_and_version(
         &self,
+        pid: Pid,
         soname: Option<String>,
     ) -> Result<(PathBuf, String, Option<SoVersion>)> {
         let mut file_path = PathBuf::from(self.name.clone().unwrap_or_default());
@@ -292,7 +343,7 @@ impl MappingInfo {
         // filesystem name of the module.
 
         // Just use the filesystem name if no SONAME is present.
-        let Some(file_name) = soname.or_else(|| self.so_name().ok()) else {
+        let Some(file_name) = soname.or_else(|| self.so_name(pid).ok()) else {
             //   file_path := /path/to/libname.so
             //   file_name := libname.so
             let file_name = file_path
@@ -359,6 +410,73 @@ impl MappingInfo {
     pub fn is_writable(&self) -> bool {
         self.permissions.contains(MMPermissions::WRITE)
     }
+
+    /// Whether the name of the mapping matches `pattern`, in which `*` matches
+    /// any sequence of characters and `?` any single one. Patterns with a `/`
+    /// are matched against the whole name, others against the file name only,
+    /// e.g. `libfoo*.so*` or `[heap]`.
+    pub fn name_matches(&self, pattern: &str) -> bool {
+        name_matches(self.name.as_deref(), pattern)
+    }
+}
+
+/// The name of a mapping of `/proc/$pid/maps`, `None` for anonymous ones.
+pub(crate) fn mapping_name(pathname: &MMapPath) -> Option<OsString> {
+    match pathname {
+        MMapPath::Path(p) => Some(sanitize_path(p.clone().into())),
+        MMapPath::Heap => Some("[heap]".into()),
+        MMapPath::Stack => Some("[stack]".into()),
+        MMapPath::TStack(i) => Some(format!("[stack:{i}]").into()),
+        MMapPath::Vdso => Some("[vdso]".into()),
+        MMapPath::Vvar => Some("[vvar]".into()),
+        MMapPath::Vsyscall => Some("[vsyscall]".into()),
+        MMapPath::Rollup => Some("[rollup]".into()),
+        MMapPath::Vsys(i) => Some(format!("/SYSV{i:x}").into()),
+        MMapPath::Other(n) => Some(format!("[{n}]").into()),
+        MMapPath::Anonymous => None,
+    }
+}
+
+/// See [`MappingInfo::name_matches`].
+pub(crate) fn name_matches(name: Option<&OsStr>, pattern: &str) -> bool {
+    let Some(name) = name else {
+        return false;
+    };
+    let name = name.as_bytes();
+    let name = if pattern.contains('/') {
+        name
+    } else {
+        name.rsplit(|c| *c == b'/').next().unwrap_or(name)
+    };
+    glob_match(pattern.as_bytes(), name)
+}
+
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    // The position after the last `*`, and the position in the name it
+    // matched up to, to backtrack to when the rest doesn't match
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                p += 1;
+                backtrack = Some((p, n));
+            }
+            Some(c) if *c == b'?' || *c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star_p, star_n)) => {
+                    p = star_p;
+                    n = star_n + 1;
+                    backtrack = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
 }
 
 /// Version metadata retrieved from an .so filename
@@ -539,6 +657,10 @@ ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsysca
                 | MMPermissions::EXECUTE
                 | MMPermissions::PRIVATE,
             name: Some("/usr/bin/cat".into()),
+            deleted: false,
+            device: (0x00, 0x31),
+            inode: 4750073,
+            first_mapping_end: 0x5597483fe000,
         };
 
         assert_eq!(mappings[0], cat_map);
@@ -553,6 +675,10 @@ ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsysca
             offset: 0,
             permissions: MMPermissions::READ | MMPermissions::WRITE | MMPermissions::PRIVATE,
             name: Some("[heap]".into()),
+            deleted: false,
+            device: (0, 0),
+            inode: 0,
+            first_mapping_end: 0x559749b2f000,
         };
 
         assert_eq!(mappings[1], heap_map);
@@ -567,6 +693,10 @@ ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsysca
             offset: 0,
             permissions: MMPermissions::READ | MMPermissions::WRITE | MMPermissions::PRIVATE,
             name: None,
+            deleted: false,
+            device: (0, 0),
+            inode: 0,
+            first_mapping_end: 0x7efd968f5000,
         };
 
         assert_eq!(mappings[2], empty_map);
@@ -586,6 +716,10 @@ ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsysca
             offset: 0,
             permissions: MMPermissions::READ | MMPermissions::EXECUTE | MMPermissions::PRIVATE,
             name: Some("linux-gate.so".into()),
+            deleted: false,
+            device: (0, 0),
+            inode: 0,
+            first_mapping_end: 0x7ffc6e0f9000,
         };
 
         assert_eq!(mappings[21], gate_map);
@@ -649,6 +783,10 @@ ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsysca
                 | MMPermissions::EXECUTE
                 | MMPermissions::PRIVATE,
             name: Some("/lib64/libc-2.32.so".into()),
+            deleted: false,
+            device: (0x00, 0x31),
+            inode: 4996104,
+            first_mapping_end: 0x7efd96bea000,
         };
 
         assert_eq!(mappings[6], gate_map);
@@ -681,6 +819,10 @@ a4840000-a4873000 rw-p 09021000 08:12 393449     /data/app/org.mozilla.firefox-1
                 | MMPermissions::EXECUTE
                 | MMPermissions::PRIVATE,
             name: Some("/data/app/org.mozilla.firefox-1/lib/x86/libxul.so".into()),
+            deleted: false,
+            device: (0x08, 0x12),
+            inode: 393449,
+            first_mapping_end: 0x9b931000,
         };
 
         assert_eq!(mappings[0], gate_map);
@@ -699,7 +841,7 @@ a4840000-a4873000 rw-p 09021000 08:12 393449     /data/app/org.mozilla.firefox-1
         assert_eq!(mappings.len(), 1);
 
         let (file_path, file_name, _version) = mappings[0]
-            .get_mapping_effective_path_name_and_version(None)
+            .get_mapping_effective_path_name_and_version(std::process::id() as Pid, None)
             .expect("Couldn't get effective name for mapping");
         assert_eq!(file_name, "libmozgtk.so");
         assert_eq!(file_path, PathBuf::from("/home/martin/Documents/mozilla/devel/mozilla-central/obj/widget/gtk/mozgtk/gtk3/libmozgtk.so"));
@@ -750,4 +892,61 @@ a4840000-a4873000 rw-p 09021000 08:12 393449     /data/app/org.mozilla.firefox-1
         );
         assert_eq!(mappings[2].name, None);
     }
+
+    #[test]
+    fn test_deleted_and_memfd_mappings() {
+        let mappings = get_mappings_for(
+            "\
+10000000-20000000 r-xp 00000000 00:3e 27136458                   /usr/bin/app (deleted)
+30000000-40000000 r-xp 00000000 00:01 1047                       /memfd:jit (deleted)
+50000000-60000000 r-xp 00000000 00:3e 27136459                   /usr/lib/libfoo.so",
+            0x7ffe091bf000,
+        );
+
+        assert_eq!(mappings.len(), 3);
+        assert_eq!(mappings[0].name, Some("/usr/bin/app".into()));
+        assert!(mappings[0].deleted && !mappings[0].is_memfd());
+        assert!(mappings[1].deleted && mappings[1].is_memfd());
+        assert!(!mappings[2].deleted && !mappings[2].is_memfd());
+
+        // A file at the path of a deleted one is not the one that was mapped
+        let pid = std::process::id() as Pid;
+        assert!(!mappings[0]
+            .file_paths(pid)
+            .iter()
+            .any(|path| path.starts_with(format!("/proc/{pid}/root"))));
+        assert_eq!(
+            mappings[2].file_paths(pid)[0],
+            Path::new(&format!("/proc/{pid}/map_files/50000000-60000000"))
+        );
+        assert_eq!(
+            mappings[2].file_paths(pid).last().unwrap(),
+            Path::new(&format!("/proc/{pid}/root/usr/lib/libfoo.so"))
+        );
+    }
+
+    #[test]
+    fn test_name_matches() {
+        let mappings = get_mappings_for(LINES, 0xffffffffff600000);
+        let cat = &mappings[0];
+        assert!(cat.name_matches("cat"));
+        assert!(cat.name_matches("c?t"));
+        assert!(cat.name_matches("*"));
+        assert!(cat.name_matches("/usr/*/cat"));
+        assert!(!cat.name_matches("/usr/bin/ca"));
+        assert!(!cat.name_matches("bin/cat"));
+
+        let heap = mappings
+            .iter()
+            .find(|mapping| mapping.name.as_deref() == Some("[heap]".as_ref()))
+            .unwrap();
+        assert!(heap.name_matches("[heap]"));
+        assert!(heap.name_matches("[*]"));
+
+        assert!(glob_match(b"lib*.so*", b"libfoo.so.1.2"));
+        assert!(glob_match(b"*a*b", b"aaxab"));
+        assert!(!glob_match(b"*a*b", b"aaxba"));
+        assert!(glob_match(b"", b""));
+        assert!(!glob_match(b"", b"a"));
+    }
 }
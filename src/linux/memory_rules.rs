@@ -0,0 +1,161 @@
+//! Declarative inclusion of process memory, see [`MemoryRule`].
+
+use crate::linux::maps_reader;
+use procfs_core::process::{MMPermissions, MemoryMap};
+use std::ops::Range;
+
+/// A rule to include the memory of the mappings whose name matches a pattern
+/// and whose permissions include a mask, e.g. `[heap]`, `/dev/shm/ourbuf*`
+/// or `[anon:our-arena]`, without knowing their addresses in advance.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryRule {
+    /// The pattern names of mappings must match, see
+    /// [`MappingInfo::name_matches`](maps_reader::MappingInfo::name_matches).
+    /// Any mapping matches without one, including anonymous ones.
+    pub name_pattern: Option<String>,
+    /// The permissions mappings must all have.
+    pub permissions: MMPermissions,
+    /// The maximum number of bytes to include for all the mappings the rule
+    /// matches. The start of the mappings is kept.
+    pub max_size: usize,
+    /// Mappings several rules match are only included by the one with the
+    /// highest priority, or the first one among them.
+    pub priority: i32,
+}
+
+impl MemoryRule {
+    /// Include up to `max_size` bytes of the mappings whose name matches
+    /// `pattern`.
+    pub fn name(pattern: impl Into<String>, max_size: usize) -> Self {
+        Self {
+            name_pattern: Some(pattern.into()),
+            max_size,
+            ..Default::default()
+        }
+    }
+
+    /// Include up to `max_size` bytes of the mappings with all of the
+    /// `permissions`.
+    pub fn permissions(permissions: MMPermissions, max_size: usize) -> Self {
+        Self {
+            permissions,
+            max_size,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    pub(crate) fn matches(&self, map: &MemoryMap) -> bool {
+        map.perms.contains(self.permissions)
+            && self.name_pattern.as_deref().is_none_or(|pattern| {
+                maps_reader::name_matches(
+                    maps_reader::mapping_name(&map.pathname).as_deref(),
+                    pattern,
+                )
+            })
+    }
+}
+
+pub type MemoryRuleList = Vec<MemoryRule>;
+
+/// The address ranges `rules` include from the readable entries of
+/// `/proc/$pid/maps`. These aren't merged like [`maps_reader::MappingInfo`]s
+/// are, so that every mapping is matched with its own permissions.
+pub(crate) fn ranges_to_include(rules: &[MemoryRule], maps: &[MemoryMap]) -> Vec<Range<usize>> {
+    let mut rules: Vec<&MemoryRule> = rules.iter().collect();
+    // Stable, so the first of the rules with the same priority wins
+    rules.sort_by_key(|rule| std::cmp::Reverse(rule.priority));
+
+    let mut budgets: Vec<usize> = rules.iter().map(|rule| rule.max_size).collect();
+    let mut ranges = Vec::new();
+    for map in maps
+        .iter()
+        .filter(|map| map.perms.contains(MMPermissions::READ))
+    {
+        let Some(idx) = rules.iter().position(|rule| rule.matches(map)) else {
+            continue;
+        };
+        let start_address = map.address.0 as usize;
+        let size = ((map.address.1 - map.address.0) as usize).min(budgets[idx]);
+        if size > 0 {
+            budgets[idx] -= size;
+            ranges.push(start_address..start_address + size);
+        }
+    }
+    ranges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use procfs_core::{process::MemoryMaps, FromRead};
+
+    const LINES: &str = "\
+08048000-0804a000 r--p 00000000 00:31 4750073                    /usr/bin/cat
+09b0e000-09b2f000 rw-p 00000000 00:00 0                          [heap]
+b68d3000-b68f5000 rw-p 00000000 00:00 0                          [anon:our-arena]
+b68f5000-b68f6000 ---p 00000000 00:00 0                          [anon:our-arena]
+b694a000-b6bc2000 rw-s 00000000 00:19 5004373                    /dev/shm/ourbuf-1
+b6bc2000-b6bc4000 rw-p 00000000 00:00 0 
+";
+
+    fn maps() -> Vec<MemoryMap> {
+        MemoryMaps::from_read(LINES.as_bytes())
+            .unwrap()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn test_ranges_to_include() {
+        let maps = maps();
+
+        let rules = [
+            MemoryRule::name("[heap]", 0x1000),
+            MemoryRule::name("/dev/shm/ourbuf*", usize::MAX),
+        ];
+        assert_eq!(
+            ranges_to_include(&rules, &maps),
+            [0x09b0e000..0x09b0f000, 0xb694a000..0xb6bc2000]
+        );
+
+        // The budget is shared by the mappings of the rule
+        let rules = [MemoryRule::permissions(
+            MMPermissions::READ | MMPermissions::WRITE,
+            0x22000,
+        )];
+        assert_eq!(
+            ranges_to_include(&rules, &maps),
+            [0x09b0e000..0x09b2f000, 0xb68d3000..0xb68d4000]
+        );
+
+        // Shared mappings aren't merged with private ones
+        let rules = [MemoryRule::permissions(MMPermissions::SHARED, usize::MAX)];
+        assert_eq!(
+            ranges_to_include(&rules, &maps),
+            [Range {
+                start: 0xb694a000,
+                end: 0xb6bc2000
+            }]
+        );
+    }
+
+    #[test]
+    fn test_priority() {
+        let maps = maps();
+        let rules = [
+            MemoryRule::permissions(MMPermissions::WRITE, 0x1000),
+            MemoryRule::name("[anon:*]", usize::MAX).with_priority(1),
+        ];
+        // The guard page of the arena isn't readable, and the arena doesn't
+        // use up the budget of the first rule
+        assert_eq!(
+            ranges_to_include(&rules, &maps),
+            [0x09b0e000..0x09b0f000, 0xb68d3000..0xb68f5000]
+        );
+    }
+}
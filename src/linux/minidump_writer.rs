@@ -6,10 +6,22 @@ use {
         linux::{
             app_memory::AppMemoryList,
             crash_context::CrashContext,
+            data_segments::DataSegmentsPolicy,
             dso_debug,
+            errors::DumperError,
             errors::WriterError,
+            ext_streams::{
+                ExtStreamType, MDRawModuleInfo, MDRawSanitizedRegion, MDRawSignalFrame,
+                MDRawSignalStack, MDRawStackOverflow, SanitizedRegionKind,
+                EXCEPTION_CODE_RUST_PANIC,
+            },
+            jit_code::{JitCodeList, JitSymbol},
             maps_reader::{MappingInfo, MappingList},
+            memory_rules::MemoryRuleList,
+            panic_hook::RustPanic,
             ptrace_dumper::PtraceDumper,
+            register_memory::RegisterMemoryPolicy,
+            sanitization::{SanitizationPolicy, ScrubMode},
             sections::*,
         },
         mem_writer::{Buffer, MemoryArrayWriter, MemoryWriter, MemoryWriterError},
@@ -29,6 +41,55 @@ pub enum CrashingThreadContext {
     CrashContextPlusAddress((MDLocationDescriptor, usize)),
 }
 
+/// How much detail the optional smaps stream should contain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SmapsMode {
+    /// One entry per mapping, read from `/proc/$pid/smaps`.
+    Mappings,
+    /// A single process-wide entry, read from `/proc/$pid/smaps_rollup`. This
+    /// is much cheaper for processes with many mappings.
+    Rollup,
+}
+
+/// Which pid namespace the thread ids written to the minidump belong to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ThreadIdNamespace {
+    /// The ids as seen by the dumping process.
+    #[default]
+    Dumper,
+    /// The ids as seen by the process itself, which differ from the ones the
+    /// dumper sees if the process runs in its own pid namespace, e.g. in a
+    /// container. Threads whose id can't be translated keep the dumper's id.
+    Process,
+}
+
+/// Where the module list comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ModuleSource {
+    /// The file backed mappings in `/proc/$pid/maps` that contain an ELF image.
+    #[default]
+    Maps,
+    /// The objects in the dynamic linker's `link_map`. Mapped modules the
+    /// linker doesn't know about are left out, and reported as soft errors
+    /// along with objects that aren't mapped. Falls back to [`Self::Maps`] if
+    /// the `link_map` can't be read, e.g. for static executables.
+    LinkMap,
+}
+
+/// Why a dump was requested, for dumps that aren't written for a crash, e.g.
+/// for failed assertions, watchdogs or panics.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DumpReason {
+    /// The exception code of the exception stream, in place of
+    /// `DUMP_REQUESTED`
+    pub exception_code: u32,
+    /// A human readable description, e.g. the panic message
+    pub reason: String,
+    /// Arbitrary parameters. Only the first 15 fit in the exception stream,
+    /// all of them are written to the dump reason stream.
+    pub parameters: Vec<u64>,
+}
+
 /// The default timeout after a `SIGSTOP` after which minidump writing proceeds
 /// regardless of the process state
 pub const STOP_TIMEOUT: Duration = Duration::from_millis(100);
@@ -42,12 +103,34 @@ pub struct MinidumpWriter {
     pub user_mapping_list: MappingList,
     pub app_memory: AppMemoryList,
     pub memory_blocks: Vec<MDMemoryDescriptor>,
+    pub module_infos: Vec<MDRawModuleInfo>,
+    pub jit_code: JitCodeList,
+    pub jit_symbols: Vec<JitSymbol>,
     pub principal_mapping: Option<MappingInfo>,
     pub sanitize_stack: bool,
+    pub sanitization_policy: SanitizationPolicy,
+    pub sanitized_regions: Vec<MDRawSanitizedRegion>,
+    pub stack_overflows: Vec<MDRawStackOverflow>,
+    pub recover_signal_frames: bool,
+    pub signal_frames: Vec<MDRawSignalFrame>,
+    pub capture_signal_stack: bool,
+    pub signal_stack: Option<MDRawSignalStack>,
+    pub register_memory_policy: Option<RegisterMemoryPolicy>,
+    pub thread_pointer_window: usize,
+    pub data_segments_policy: Option<DataSegmentsPolicy>,
+    pub memory_rules: MemoryRuleList,
+    pub capture_module_metadata: bool,
+    pub hash_module_files: bool,
+    pub ingest_jit_code: bool,
     pub crash_context: Option<CrashContext>,
+    pub dump_reason: Option<DumpReason>,
+    pub rust_panic: Option<RustPanic>,
     pub crashing_thread_context: CrashingThreadContext,
     pub stop_timeout: Duration,
     pub direct_auxv_dump_info: Option<DirectAuxvDumpInfo>,
+    pub smaps_mode: Option<SmapsMode>,
+    pub thread_id_namespace: ThreadIdNamespace,
+    pub module_source: ModuleSource,
 }
 
 // This doesn't work yet:
@@ -72,12 +155,34 @@ impl MinidumpWriter {
             user_mapping_list: MappingList::new(),
             app_memory: AppMemoryList::new(),
             memory_blocks: Vec::new(),
+            module_infos: Vec::new(),
+            jit_code: JitCodeList::new(),
+            jit_symbols: Vec::new(),
             principal_mapping: None,
             sanitize_stack: false,
+            sanitization_policy: SanitizationPolicy::default(),
+            sanitized_regions: Vec::new(),
+            stack_overflows: Vec::new(),
+            recover_signal_frames: false,
+            signal_frames: Vec::new(),
+            capture_signal_stack: false,
+            signal_stack: None,
+            register_memory_policy: None,
+            thread_pointer_window: 0,
+            data_segments_policy: None,
+            memory_rules: Vec::new(),
+            capture_module_metadata: false,
+            hash_module_files: false,
+            ingest_jit_code: false,
             crash_context: None,
+            dump_reason: None,
+            rust_panic: None,
             crashing_thread_context: CrashingThreadContext::None,
             stop_timeout: STOP_TIMEOUT,
             direct_auxv_dump_info: None,
+            smaps_mode: None,
+            thread_id_namespace: ThreadIdNamespace::default(),
+            module_source: ModuleSource::default(),
         }
     }
 
@@ -101,11 +206,38 @@ impl MinidumpWriter {
         self
     }
 
+    /// Regions of JIT code to write as modules, along with their symbols.
+    pub fn set_jit_code(&mut self, jit_code: JitCodeList) -> &mut Self {
+        self.jit_code = jit_code;
+        self
+    }
+
     pub fn set_crash_context(&mut self, crash_context: CrashContext) -> &mut Self {
         self.crash_context = Some(crash_context);
         self
     }
 
+    /// Why the dump is requested, written to the exception stream if there is
+    /// no crash context, and to the dump reason stream.
+    pub fn set_dump_reason(&mut self, dump_reason: DumpReason) -> &mut Self {
+        self.dump_reason = Some(dump_reason);
+        self
+    }
+
+    /// The Rust panic the dump is written for. Its message becomes the dump
+    /// reason, with [`EXCEPTION_CODE_RUST_PANIC`] as the exception code, and
+    /// the panicking thread the blamed thread.
+    pub fn set_rust_panic(&mut self, rust_panic: RustPanic) -> &mut Self {
+        self.blamed_thread = rust_panic.thread_id;
+        self.dump_reason = Some(DumpReason {
+            exception_code: EXCEPTION_CODE_RUST_PANIC,
+            reason: rust_panic.message.clone(),
+            parameters: Vec::new(),
+        });
+        self.rust_panic = Some(rust_panic);
+        self
+    }
+
     pub fn skip_stacks_if_mapping_unreferenced(&mut self) -> &mut Self {
         self.skip_stacks_if_mapping_unreferenced = true; // Off by default
         self
@@ -116,6 +248,86 @@ impl MinidumpWriter {
         self
     }
 
+    /// Sanitize the stacks, and the other memory `policy` covers, according
+    /// to `policy` rather than the default one.
+    pub fn set_sanitization_policy(&mut self, policy: SanitizationPolicy) -> &mut Self {
+        self.sanitization_policy = policy;
+        self.sanitize_stack = true;
+        self
+    }
+
+    /// Include the ELF header, program headers, notes, dynamic section and
+    /// `.eh_frame_hdr` of every module in the memory list, so that they can
+    /// be used when the module files aren't available.
+    pub fn capture_module_metadata(&mut self) -> &mut Self {
+        self.capture_module_metadata = true; // Off by default
+        self
+    }
+
+    /// Hash the file of every module, so that modules can be identified by
+    /// their contents even without a build id. This reads every module file
+    /// in full.
+    pub fn hash_module_files(&mut self) -> &mut Self {
+        self.hash_module_files = true; // Off by default
+        self
+    }
+
+    /// Record the registers and stack a signal interrupted for threads that
+    /// are running a signal handler, rather than those of the handler. Only
+    /// supported on x86_64.
+    pub fn recover_signal_frames(&mut self) -> &mut Self {
+        self.recover_signal_frames = true; // Off by default
+        self
+    }
+
+    /// Include the alternate signal stack the signal handler of the crashing
+    /// thread runs on, up to 1 MiB of it, within the minidump size limit.
+    pub fn capture_signal_stack(&mut self) -> &mut Self {
+        self.capture_signal_stack = true; // Off by default
+        self
+    }
+
+    /// Include windows of memory around the register values of the crashing
+    /// thread, or of every thread, that point into readable memory, and
+    /// around the fault address. Off by default.
+    pub fn set_register_memory_policy(&mut self, policy: RegisterMemoryPolicy) -> &mut Self {
+        self.register_memory_policy = Some(policy);
+        self
+    }
+
+    /// Include `size` bytes of memory centered on the thread pointer of every
+    /// thread, which is recorded as its TEB. With glibc this covers the
+    /// `struct pthread` and the static TLS block of the thread, which
+    /// surround it. Off by default.
+    pub fn set_thread_pointer_window(&mut self, size: usize) -> &mut Self {
+        self.thread_pointer_window = size;
+        self
+    }
+
+    /// Include the writable segments (`.data`, `.bss`, `.got`...) of the
+    /// modules `policy` selects in the memory list. Off by default.
+    pub fn set_data_segments_policy(&mut self, policy: DataSegmentsPolicy) -> &mut Self {
+        self.data_segments_policy = Some(policy);
+        self
+    }
+
+    /// Include the memory of the mappings the `rules` match, see
+    /// [`MemoryRule`](crate::memory_rules::MemoryRule).
+    pub fn set_memory_rules(&mut self, rules: MemoryRuleList) -> &mut Self {
+        self.memory_rules = rules;
+        self
+    }
+
+    /// Read the JIT code of the process from `/tmp/perf-<pid>.map` and from
+    /// the GDB JIT interface (`__jit_debug_descriptor`, which has to be in the
+    /// dynamic symbols of a module), in addition to the regions set with
+    /// [`Self::set_jit_code`]. Regions that overlap a module or an earlier
+    /// region are left out.
+    pub fn ingest_jit_code_info(&mut self) -> &mut Self {
+        self.ingest_jit_code = true; // Off by default
+        self
+    }
+
     /// Sets the timeout after `SIGSTOP` is sent to the process, if the process
     /// has not stopped by the time the timeout has reached, we proceed with
     /// minidump generation
@@ -139,6 +351,71 @@ impl MinidumpWriter {
         self
     }
 
+    /// Include per-mapping memory accounting (resident, dirty, swapped...) read
+    /// from `/proc/{pid}/smaps`, or a process-wide summary read from
+    /// `/proc/{pid}/smaps_rollup`, in an extra stream. Off by default.
+    pub fn set_smaps_mode(&mut self, mode: SmapsMode) -> &mut Self {
+        self.smaps_mode = Some(mode);
+        self
+    }
+
+    /// Report thread ids as seen by the dumper (the default), or as seen by the
+    /// process itself in its pid namespace.
+    pub fn set_thread_id_namespace(&mut self, namespace: ThreadIdNamespace) -> &mut Self {
+        self.thread_id_namespace = namespace;
+        self
+    }
+
+    /// Build the module list from the mappings (the default), or from the
+    /// dynamic linker's `link_map`.
+    pub fn set_module_source(&mut self, source: ModuleSource) -> &mut Self {
+        self.module_source = source;
+        self
+    }
+
+    /// The id `tid` is written to the minidump with, see [`ThreadIdNamespace`].
+    pub(crate) fn reported_thread_id(&self, dumper: &PtraceDumper, tid: Pid) -> Pid {
+        match self.thread_id_namespace {
+            ThreadIdNamespace::Dumper => tid,
+            ThreadIdNamespace::Process => dumper
+                .threads
+                .iter()
+                .find(|thread| thread.tid == tid)
+                .and_then(|thread| thread.ns_tid)
+                .unwrap_or(tid),
+        }
+    }
+
+    /// Sanitize `memory`, a copy of the memory at `start_address`, according to
+    /// the policy, and record what was scrubbed. See
+    /// [`PtraceDumper::sanitize_memory`] for `address` and `sp_offset`.
+    pub(crate) fn sanitize_memory(
+        &mut self,
+        dumper: &PtraceDumper,
+        kind: SanitizedRegionKind,
+        memory: &mut [u8],
+        start_address: usize,
+        address: usize,
+        sp_offset: usize,
+    ) -> std::result::Result<(), DumperError> {
+        let stats =
+            dumper.sanitize_memory(&self.sanitization_policy, memory, address, sp_offset)?;
+        self.sanitized_regions.push(MDRawSanitizedRegion {
+            start_of_memory_range: start_address as u64,
+            data_size: memory.len() as u64,
+            kind: kind as u32,
+            scrub_mode: match self.sanitization_policy.scrub_mode {
+                ScrubMode::Mark => 0,
+                ScrubMode::Zero => 1,
+            },
+            kept_small_ints: stats.kept_small_ints,
+            kept_pointers: stats.kept_pointers,
+            scrubbed_words: stats.scrubbed_words,
+            zeroed_bytes: stats.zeroed_bytes,
+        });
+        Ok(())
+    }
+
     /// Generates a minidump and writes to the destination provided. Returns the in-memory
     /// version of the minidump as well.
     pub fn dump(&mut self, destination: &mut (impl Write + Seek)) -> Result<Vec<u8>> {
@@ -148,6 +425,15 @@ impl MinidumpWriter {
             .map(AuxvDumpInfo::from)
             .unwrap_or_default();
 
+        // Start over from what a previous dump collected
+        self.memory_blocks.clear();
+        self.module_infos.clear();
+        self.jit_symbols.clear();
+        self.sanitized_regions.clear();
+        self.stack_overflows.clear();
+        self.signal_frames.clear();
+        self.signal_stack = None;
+
         let mut soft_errors = ErrorList::default();
 
         let mut dumper = PtraceDumper::new_report_soft_errors(
@@ -246,7 +532,7 @@ impl MinidumpWriter {
     ) -> Result<()> {
         // A minidump file contains a number of tagged streams. This is the number
         // of streams which we write.
-        let num_writers = 18u32;
+        let num_writers = 29u32;
 
         let mut header_section = MemoryWriter::<MDRawHeader>::alloc(buffer)?;
 
@@ -274,16 +560,103 @@ impl MinidumpWriter {
         let dirent = thread_list_stream::write(self, buffer, dumper)?;
         dir_section.write_to_file(buffer, Some(dirent))?;
 
-        let dirent = mappings::write(self, buffer, dumper)?;
+        let dirent = mappings::write(
+            self,
+            buffer,
+            dumper,
+            soft_errors.subwriter(WriterError::WriteMappingsErrors),
+        )?;
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
+        let dirent = module_info_stream::write(self, buffer)?;
         dir_section.write_to_file(buffer, Some(dirent))?;
 
-        app_memory::write(self, buffer)?;
+        let dirent = jit_symbols_stream::write(self, buffer)?;
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
+        app_memory::write(self, buffer, dumper)?;
         dir_section.write_to_file(buffer, None)?;
 
+        if self.capture_module_metadata {
+            module_metadata::write(
+                self,
+                buffer,
+                dumper,
+                soft_errors.subwriter(WriterError::WriteModuleMetadataErrors),
+            )?;
+            dir_section.write_to_file(buffer, None)?;
+        }
+
+        if let Some(policy) = self.data_segments_policy.clone() {
+            data_segments::write(
+                self,
+                buffer,
+                dumper,
+                &policy,
+                soft_errors.subwriter(WriterError::WriteDataSegmentsErrors),
+            )?;
+            dir_section.write_to_file(buffer, None)?;
+        }
+
+        if !self.memory_rules.is_empty() {
+            memory_rules::write(
+                self,
+                buffer,
+                dumper,
+                soft_errors.subwriter(WriterError::WriteMemoryRulesErrors),
+            )?;
+            dir_section.write_to_file(buffer, None)?;
+        }
+
+        let dirent = if self.sanitize_stack {
+            sanitization_stats_stream::write(self, buffer)?
+        } else {
+            Default::default()
+        };
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
         let dirent = memory_list_stream::write(self, buffer)?;
         dir_section.write_to_file(buffer, Some(dirent))?;
 
-        let dirent = exception_stream::write(self, buffer)?;
+        let dirent = exception_stream::write(self, buffer, dumper)?;
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
+        let dirent = match &self.dump_reason {
+            Some(dump_reason) => dump_reason_stream::write(dump_reason, buffer)?,
+            None => Default::default(),
+        };
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
+        let dirent = match &self.rust_panic {
+            Some(rust_panic) => rust_panic_stream::write(rust_panic, buffer)?,
+            None => Default::default(),
+        };
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
+        let dirent = if self.stack_overflows.is_empty() {
+            Default::default()
+        } else {
+            stack_overflow_stream::write(self, buffer)?
+        };
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
+        let dirent = if self.recover_signal_frames {
+            signal_frames_stream::write(self, buffer)?
+        } else {
+            Default::default()
+        };
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
+        let dirent = match &self.signal_stack {
+            Some(signal_stack) => {
+                let stream = MemoryWriter::alloc_with_val(buffer, *signal_stack)?;
+                MDRawDirectory {
+                    stream_type: ExtStreamType::LinuxSignalStack.into(),
+                    location: stream.location(),
+                }
+            }
+            None => Default::default(),
+        };
         dir_section.write_to_file(buffer, Some(dirent))?;
 
         let dirent = systeminfo_stream::write(
@@ -295,6 +668,18 @@ impl MinidumpWriter {
         let dirent = memory_info_list_stream::write(self, buffer)?;
         dir_section.write_to_file(buffer, Some(dirent))?;
 
+        let dirent = match self.smaps_mode {
+            Some(mode) => match smaps_stream::write(self, buffer, mode) {
+                Ok(dirent) => dirent,
+                Err(e) => {
+                    soft_errors.push(WriterError::WriteSmapsStreamFailed(e));
+                    Default::default()
+                }
+            },
+            None => Default::default(),
+        };
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
         let dirent = match self.write_file(buffer, "/proc/cpuinfo") {
             Ok(location) => MDRawDirectory {
                 stream_type: MDStreamType::LinuxCpuInfo as u32,
@@ -320,6 +705,24 @@ impl MinidumpWriter {
         };
         dir_section.write_to_file(buffer, Some(dirent))?;
 
+        let dirent = match container_info_stream::write(self, buffer) {
+            Ok(dirent) => dirent,
+            Err(e) => {
+                soft_errors.push(WriterError::WriteContainerInfoStreamFailed(e));
+                Default::default()
+            }
+        };
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
+        let dirent = match security_context_stream::write(self, buffer) {
+            Ok(dirent) => dirent,
+            Err(e) => {
+                soft_errors.push(WriterError::WriteSecurityContextStreamFailed(e));
+                Default::default()
+            }
+        };
+        dir_section.write_to_file(buffer, Some(dirent))?;
+
         let dirent = match self
             .write_file(buffer, "/etc/lsb-release")
             .or_else(|_| self.write_file(buffer, "/etc/os-release"))
@@ -408,7 +811,7 @@ impl MinidumpWriter {
         };
         dir_section.write_to_file(buffer, Some(dirent))?;
 
-        let dirent = thread_names_stream::write(buffer, dumper)?;
+        let dirent = thread_names_stream::write(self, buffer, dumper)?;
         dir_section.write_to_file(buffer, Some(dirent))?;
 
         let dirent = match handle_data_stream::write(self, buffer) {
@@ -10,6 +10,10 @@ use std::{borrow::Cow, ffi::CStr};
 type Buf<'buf> = Cow<'buf, [u8]>;
 
 const NOTE_SECTION_NAME: &[u8] = b".note.gnu.build-id\0";
+const DEBUGLINK_SECTION_NAME: &[u8] = b".gnu_debuglink\0";
+const DEBUGALTLINK_SECTION_NAME: &[u8] = b".gnu_debugaltlink\0";
+/// Upper bound of the length of a hash chain, in case the hash table is corrupt.
+const MAX_HASH_CHAIN_LEN: usize = 4096;
 
 pub struct ProcessReader {
     inner: MemReader,
@@ -137,7 +141,7 @@ fn section_header_with_name<'sc>(
             log::warn!("invalid sh_name offset for {:?}", name);
             continue;
         }
-        if sh_name + name.len() as u64 >= strtab_section_header.sh_size {
+        if sh_name + name.len() as u64 > strtab_section_header.sh_size {
             // This can't be a match.
             continue;
         }
@@ -246,6 +250,55 @@ impl ReadFromModule for SoName {
     }
 }
 
+/// The separate debug files of the module.
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct DebugLinks {
+    /// The file name and CRC32 of the debug file, from `.gnu_debuglink`
+    pub debuglink: Option<(String, u32)>,
+    /// The file name and build id of the supplementary debug file shared with
+    /// other modules, from `.gnu_debugaltlink`
+    pub debugaltlink: Option<(String, Vec<u8>)>,
+}
+
+impl ReadFromModule for DebugLinks {
+    fn read_from_module(module_memory: ProcessMemory<'_>) -> Result<Self, Error> {
+        let mut reader = ModuleReader::new(module_memory)?;
+        let links = DebugLinks {
+            debuglink: reader.debuglink().ok(),
+            debugaltlink: reader.debugaltlink().ok(),
+        };
+        if links == DebugLinks::default() {
+            return Err(Error::NoDebugLink);
+        }
+        Ok(links)
+    }
+}
+
+/// The regions of the module that describe its layout: the ELF header, the
+/// program headers, and the `PT_NOTE`, `PT_DYNAMIC` and `PT_GNU_EH_FRAME`
+/// segments. Offsets are relative to the start of the module, sorted, and
+/// don't overlap.
+#[derive(Default, Clone, Debug)]
+pub struct ElfMetadata(pub Vec<std::ops::Range<u64>>);
+
+impl ReadFromModule for ElfMetadata {
+    fn read_from_module(module_memory: ProcessMemory<'_>) -> Result<Self, Error> {
+        let mut reader = ModuleReader::new(module_memory)?;
+        reader.metadata_regions().map(ElfMetadata)
+    }
+}
+
+/// The writable segments of the module (`.data`, `.bss`, `.got`...), relative
+/// to the start of the module in memory.
+pub struct DataSegments(pub Vec<std::ops::Range<u64>>);
+
+impl ReadFromModule for DataSegments {
+    fn read_from_module(module_memory: ProcessMemory<'_>) -> Result<Self, Error> {
+        let mut reader = ModuleReader::new(module_memory)?;
+        reader.data_segments().map(DataSegments)
+    }
+}
+
 pub struct ModuleReader<'buf> {
     module_memory: ProcessMemory<'buf>,
     header: elf::Header,
@@ -391,6 +444,39 @@ impl<'buf> ModuleReader<'buf> {
         }
     }
 
+    /// Read the file name and CRC32 of the debug file from `.gnu_debuglink`.
+    pub fn debuglink(&mut self) -> Result<(String, u32), Error> {
+        let data = self.read_section_by_name(DEBUGLINK_SECTION_NAME)?;
+        parse_debuglink(&data, self.context.le).ok_or(Error::NoDebugLink)
+    }
+
+    /// Read the file name and build id of the supplementary debug file from
+    /// `.gnu_debugaltlink`.
+    pub fn debugaltlink(&mut self) -> Result<(String, Vec<u8>), Error> {
+        let data = self.read_section_by_name(DEBUGALTLINK_SECTION_NAME)?;
+        parse_debugaltlink(&data).ok_or(Error::NoDebugLink)
+    }
+
+    /// Read the data of a section. Sections that aren't loaded can only be
+    /// read from the file.
+    fn read_section_by_name(&mut self, name: &[u8]) -> Result<Buf<'buf>, Error> {
+        let section_headers = self.read_section_headers()?;
+        let header = section_header_with_name(
+            &section_headers,
+            self.header.e_shstrndx as usize,
+            name,
+            &mut self.module_memory,
+        )?
+        .ok_or(Error::NoDebugLink)?;
+        if self.module_memory.is_process_memory()
+            && header.sh_flags & u64::from(elf::section_header::SHF_ALLOC) == 0
+        {
+            return Err(Error::NoDebugLink);
+        }
+        self.module_memory
+            .read(self.section_offset(header), header.sh_size)
+    }
+
     /// Generate a build id by hashing the first page of the text section.
     pub fn build_id_generate_from_text(&mut self) -> Result<Vec<u8>, Error> {
         let Some(text_header) = self
@@ -407,6 +493,189 @@ impl<'buf> ModuleReader<'buf> {
         Ok(build_id_from_bytes(&text_data))
     }
 
+    /// Get the regions describing the module layout, see [`ElfMetadata`].
+    pub fn metadata_regions(&mut self) -> Result<Vec<std::ops::Range<u64>>, Error> {
+        let program_headers = self.read_program_headers()?;
+        let base_vaddr = base_vaddr(&program_headers);
+
+        let mut regions = vec![
+            0..self.header.e_ehsize as u64,
+            self.header.e_phoff
+                ..self.header.e_phoff + self.header.e_phentsize as u64 * self.header.e_phnum as u64,
+        ];
+        for header in &program_headers {
+            if !matches!(
+                header.p_type,
+                elf::program_header::PT_NOTE
+                    | elf::program_header::PT_DYNAMIC
+                    | elf::program_header::PT_GNU_EH_FRAME
+            ) {
+                continue;
+            }
+            let (offset, size) = if self.module_memory.is_process_memory() {
+                (header.p_vaddr.saturating_sub(base_vaddr), header.p_memsz)
+            } else {
+                (header.p_offset, header.p_filesz)
+            };
+            if size > 0 {
+                regions.push(offset..offset.saturating_add(size));
+            }
+        }
+
+        regions.sort_by_key(|region| region.start);
+        let mut merged: Vec<std::ops::Range<u64>> = Vec::with_capacity(regions.len());
+        for region in regions {
+            match merged.last_mut() {
+                Some(last) if region.start <= last.end => last.end = last.end.max(region.end),
+                _ => merged.push(region),
+            }
+        }
+        Ok(merged)
+    }
+
+    /// Get the writable loadable segments, see [`DataSegments`].
+    pub fn data_segments(&mut self) -> Result<Vec<std::ops::Range<u64>>, Error> {
+        let program_headers = self.read_program_headers()?;
+        let base_vaddr = base_vaddr(&program_headers);
+        Ok(program_headers
+            .iter()
+            .filter(|header| {
+                header.p_type == elf::program_header::PT_LOAD
+                    && header.p_flags & elf::program_header::PF_W != 0
+                    && header.p_memsz > 0
+            })
+            .map(|header| {
+                let start = header.p_vaddr.saturating_sub(base_vaddr);
+                start..start.saturating_add(header.p_memsz)
+            })
+            .collect())
+    }
+
+    /// Find the symbol `name` exported by the module, and return its address
+    /// relative to the start of the module in memory. The symbol is looked up
+    /// in the hash table of the dynamic symbols, so only the few symbols of its
+    /// hash chain are read.
+    pub fn exported_symbol_offset(&mut self, name: &str) -> Result<u64, Error> {
+        let program_headers = self.read_program_headers()?;
+        let base_vaddr = base_vaddr(&program_headers);
+        let dynamic_segment_header = program_headers
+            .iter()
+            .find(|h| h.p_type == elf::program_header::PT_DYNAMIC)
+            .ok_or(Error::NoDynamicSection)?;
+
+        let dynamic_section = self.read_segment(dynamic_segment_header)?;
+
+        let mut symtab_addr = None;
+        let mut strtab_addr = None;
+        let mut strtab_size = None;
+        let mut gnu_hash_addr = None;
+        let mut hash_addr = None;
+        for dyn_ in DynIter::new(&dynamic_section, self.context) {
+            let dyn_ = dyn_?;
+            // If loaded in memory, the addresses will be altered to be absolute.
+            let addr = self.module_memory.absolute(dyn_.d_val);
+            match dyn_.d_tag {
+                elf::dynamic::DT_SYMTAB => symtab_addr = Some(addr),
+                elf::dynamic::DT_STRTAB => strtab_addr = Some(addr),
+                elf::dynamic::DT_STRSZ => strtab_size = Some(dyn_.d_val),
+                elf::dynamic::DT_GNU_HASH => gnu_hash_addr = Some(addr),
+                elf::dynamic::DT_HASH => hash_addr = Some(addr),
+                _ => (),
+            }
+        }
+        let (Some(symtab_addr), Some(strtab_addr), Some(strtab_size)) =
+            (symtab_addr, strtab_addr, strtab_size)
+        else {
+            return Err(Error::NoDynamicSymbols);
+        };
+
+        let is_symbol = |reader: &mut Self, index: u64| -> Result<Option<u64>, Error> {
+            let sym = reader.read_symbol(symtab_addr, index)?;
+            if sym.st_value == 0 || sym.st_shndx == elf::section_header::SHN_UNDEF as usize {
+                return Ok(None);
+            }
+            let name_len = name.len() as u64 + 1;
+            if sym.st_name as u64 + name_len > strtab_size {
+                return Ok(None);
+            }
+            let sym_name = reader
+                .module_memory
+                .read(strtab_addr + sym.st_name as u64, name_len)?;
+            let is_match = sym_name.strip_suffix(b"\0") == Some(name.as_bytes());
+            Ok(is_match.then(|| sym.st_value.saturating_sub(base_vaddr)))
+        };
+
+        if let Some(table) = gnu_hash_addr {
+            // nbuckets, symoffset, bloom_size and bloom_shift, followed by the
+            // bloom filter, the buckets and the chains
+            let hash = gnu_hash(name);
+            let nbuckets = self.read_u32(table)? as u64;
+            let symoffset = self.read_u32(table + 4)? as u64;
+            let bloom_size = self.read_u32(table + 8)? as u64;
+            if nbuckets == 0 {
+                return Err(Error::NoSymbol);
+            }
+            let bloom_word_size = match self.context.container {
+                Container::Little => 4,
+                Container::Big => 8,
+            };
+            let buckets = table + 16 + bloom_size * bloom_word_size;
+            let chains = buckets + nbuckets * 4;
+            let first = self.read_u32(buckets + (hash as u64 % nbuckets) * 4)? as u64;
+            if first < symoffset {
+                return Err(Error::NoSymbol);
+            }
+            for index in (first..).take(MAX_HASH_CHAIN_LEN) {
+                let chain_hash = self.read_u32(chains + (index - symoffset) * 4)?;
+                if chain_hash | 1 == hash | 1 {
+                    if let Some(offset) = is_symbol(self, index)? {
+                        return Ok(offset);
+                    }
+                }
+                // The lowest bit marks the end of the chain
+                if chain_hash & 1 != 0 {
+                    break;
+                }
+            }
+            Err(Error::NoSymbol)
+        } else if let Some(table) = hash_addr {
+            // nbucket and nchain, followed by the buckets and the chains
+            let nbucket = self.read_u32(table)? as u64;
+            if nbucket == 0 {
+                return Err(Error::NoSymbol);
+            }
+            let chains = table + 8 + nbucket * 4;
+            let mut index = self.read_u32(table + 8 + (elf_hash(name) as u64 % nbucket) * 4)?;
+            for _ in 0..MAX_HASH_CHAIN_LEN {
+                if index == 0 {
+                    break;
+                }
+                if let Some(offset) = is_symbol(self, index as u64)? {
+                    return Ok(offset);
+                }
+                index = self.read_u32(chains + index as u64 * 4)?;
+            }
+            Err(Error::NoSymbol)
+        } else {
+            Err(Error::NoDynamicSymbols)
+        }
+    }
+
+    fn read_symbol(&mut self, symtab_addr: u64, index: u64) -> Result<elf::sym::Sym, Error> {
+        use scroll::Pread;
+        let size = elf::sym::Sym::size(self.context.container) as u64;
+        let data = self.module_memory.read(symtab_addr + index * size, size)?;
+        Ok(data.pread_with(0, self.context)?)
+    }
+
+    fn read_u32(&mut self, offset: u64) -> Result<u32, Error> {
+        use scroll::Pread;
+        let data = self.module_memory.read(offset, 4)?;
+        Ok(data
+            .pread_with(0, self.context.le)
+            .map_err(goblin::error::Error::from)?)
+    }
+
     fn read_segment(&mut self, header: &elf::ProgramHeader) -> Result<Buf<'buf>, Error> {
         let (offset, size) = if self.module_memory.is_process_memory() {
             (header.p_vaddr, header.p_memsz)
@@ -500,6 +769,56 @@ impl<'buf> ModuleReader<'buf> {
     }
 }
 
+/// In memory, segments are at their virtual address relative to the one the
+/// start of the file is loaded at.
+fn base_vaddr(program_headers: &elf::ProgramHeaders) -> u64 {
+    program_headers
+        .iter()
+        .find(|h| h.p_type == elf::program_header::PT_LOAD)
+        .map_or(0, |h| h.p_vaddr.saturating_sub(h.p_offset))
+}
+
+/// The hash function of `DT_GNU_HASH` tables.
+fn gnu_hash(name: &str) -> u32 {
+    name.bytes().fold(5381u32, |hash, b| {
+        hash.wrapping_mul(33).wrapping_add(b as u32)
+    })
+}
+
+/// The hash function of `DT_HASH` tables.
+fn elf_hash(name: &str) -> u32 {
+    name.bytes().fold(0u32, |hash, b| {
+        let hash = (hash << 4).wrapping_add(b as u32);
+        let high = hash & 0xf000_0000;
+        (hash ^ (high >> 24)) & !high
+    })
+}
+
+/// `.gnu_debuglink` holds a nul terminated file name, padded to 4 bytes,
+/// followed by the CRC32 of the file.
+fn parse_debuglink(data: &[u8], endian: Endian) -> Option<(String, u32)> {
+    let name = CStr::from_bytes_until_nul(data).ok()?;
+    let crc_offset = (name.to_bytes_with_nul().len() + 3) & !3;
+    let crc: [u8; 4] = data.get(crc_offset..crc_offset + 4)?.try_into().ok()?;
+    let crc = if endian.is_little() {
+        u32::from_le_bytes(crc)
+    } else {
+        u32::from_be_bytes(crc)
+    };
+    Some((name.to_string_lossy().into_owned(), crc))
+}
+
+/// `.gnu_debugaltlink` holds a nul terminated file name followed by the build
+/// id of the file.
+fn parse_debugaltlink(data: &[u8]) -> Option<(String, Vec<u8>)> {
+    let name = CStr::from_bytes_until_nul(data).ok()?;
+    let build_id = &data[name.to_bytes_with_nul().len()..];
+    if build_id.is_empty() {
+        return None;
+    }
+    Some((name.to_string_lossy().into_owned(), build_id.to_owned()))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -576,6 +895,81 @@ mod test {
         0x58, 0x31, 0xff, 0x0f, 0x05,
     ];
 
+    #[test]
+    fn debug_links() {
+        assert_eq!(
+            parse_debuglink(b"libfoo.so.dbg\0\0\0\x78\x56\x34\x12", Endian::Little),
+            Some(("libfoo.so.dbg".into(), 0x12345678))
+        );
+        assert_eq!(
+            parse_debuglink(b"foo.dbg\0\x12\x34\x56\x78", Endian::Big),
+            Some(("foo.dbg".into(), 0x12345678))
+        );
+        assert_eq!(parse_debuglink(b"foo.dbg\0\x12\x34", Endian::Little), None);
+
+        assert_eq!(
+            parse_debugaltlink(b"/usr/lib/debug/.dwz/foo.debug\0\xde\xad\xbe\xef"),
+            Some((
+                "/usr/lib/debug/.dwz/foo.debug".into(),
+                vec![0xde, 0xad, 0xbe, 0xef]
+            ))
+        );
+        assert_eq!(parse_debugaltlink(b"foo.debug\0"), None);
+
+        let mut reader = ModuleReader::new(TINY_ELF.into()).unwrap();
+        assert!(reader.debuglink().is_err());
+        assert!(reader.debugaltlink().is_err());
+    }
+
+    #[test]
+    fn exported_symbol_offset() {
+        let mut reader = ModuleReader::new(TINY_ELF.into()).unwrap();
+        // There are no dynamic symbols, the dynamic section only has a SONAME
+        assert!(matches!(
+            reader.exported_symbol_offset("_start"),
+            Err(Error::NoDynamicSymbols)
+        ));
+
+        assert_eq!(gnu_hash(""), 0x1505);
+        assert_eq!(gnu_hash("printf"), 0x156b2bb8);
+        assert_eq!(elf_hash(""), 0);
+        assert_eq!(elf_hash("printf"), 0x077905a6);
+    }
+
+    #[test]
+    fn metadata_regions() {
+        let mut reader = ModuleReader::new(TINY_ELF.into()).unwrap();
+        let regions = reader.metadata_regions().unwrap();
+        // The ELF header and the program headers are contiguous, followed by
+        // the note and the dynamic section
+        assert_eq!(regions, [0..0xe8, 0x268..0x288, 0x2bd..0x2fd]);
+    }
+
+    #[test]
+    fn data_segments() {
+        let mut reader = ModuleReader::new(TINY_ELF.into()).unwrap();
+        // The only loadable segment is writable, and executable
+        let segments = reader.data_segments().unwrap();
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0], 0x30a..0x311);
+    }
+
+    #[test]
+    fn section_name_at_end_of_strtab() {
+        let mut reader = ModuleReader::new(TINY_ELF.into()).unwrap();
+        let section_headers = reader.read_section_headers().unwrap();
+        // `.dynstr` is the last name of the section header string table, its
+        // nul terminator is the last byte of the table
+        let header = section_header_with_name(
+            &section_headers,
+            reader.header.e_shstrndx as usize,
+            b".dynstr\0",
+            &mut reader.module_memory,
+        )
+        .unwrap();
+        assert_eq!(header.map(|header| header.sh_offset), Some(0x2fd));
+    }
+
     #[test]
     fn build_id_program_headers() {
         let mut reader = ModuleReader::new(TINY_ELF.into()).unwrap();
@@ -0,0 +1,147 @@
+//! Dumps of Rust panics.
+//!
+//! [`install_panic_hook`] captures the details of every panic as a
+//! [`RustPanic`] and hands them to a handler. The handler can send them to an
+//! out-of-process dumper, or write a dump of the current process with
+//! [`dump_current_process`] and [`MinidumpWriter::set_rust_panic`].
+
+use crate::{errors::CurrentProcessDumpError, minidump_writer::MinidumpWriter, Pid};
+use nix::{
+    sys::wait::{waitpid, WaitStatus},
+    unistd::{fork, pipe, ForkResult},
+};
+use std::os::fd::AsRawFd;
+
+/// The details of a Rust panic.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RustPanic {
+    /// The panic message, or a placeholder if the payload isn't a string
+    pub message: String,
+    /// The source file the panic originated in
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    /// The id of the panicking thread
+    pub thread_id: Pid,
+    pub thread_name: Option<String>,
+    /// The backtrace of the panicking thread
+    pub backtrace: String,
+}
+
+impl RustPanic {
+    /// Capture the details of the panic `info` describes. This must be called
+    /// on the panicking thread, e.g. in a panic hook.
+    pub fn capture(info: &std::panic::PanicHookInfo<'_>) -> Self {
+        let payload = info.payload();
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            (*message).to_owned()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "Box<dyn Any>".to_owned()
+        };
+        let (file, line, column) = info
+            .location()
+            .map(|location| {
+                (
+                    location.file().to_owned(),
+                    location.line(),
+                    location.column(),
+                )
+            })
+            .unwrap_or_default();
+
+        Self {
+            message,
+            file,
+            line,
+            column,
+            thread_id: nix::unistd::gettid().as_raw(),
+            thread_name: std::thread::current().name().map(str::to_owned),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        }
+    }
+}
+
+/// Install a panic hook that calls `handler` with the details of every panic,
+/// and then the previously installed hook.
+pub fn install_panic_hook(handler: impl Fn(&RustPanic) + Send + Sync + 'static) {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        handler(&RustPanic::capture(info));
+        previous(info);
+    }));
+}
+
+/// Write a dump of the current process with `writer` to `destination`.
+///
+/// A process can't ptrace itself, so this forks a child that writes the dump
+/// and waits for it. The process id of `writer` must be the one of the
+/// current process. The child allocates memory, which may deadlock if another
+/// thread held an allocator lock when forking, unless the allocator handles
+/// `fork` like glibc's does.
+pub fn dump_current_process(
+    writer: &mut MinidumpWriter,
+    destination: &mut std::fs::File,
+) -> Result<(), CurrentProcessDumpError> {
+    let (read_end, write_end) = pipe().map_err(CurrentProcessDumpError::PipeFailed)?;
+
+    // Safety: the child only writes the dump and exits without returning.
+    match unsafe { fork() }.map_err(CurrentProcessDumpError::ForkFailed)? {
+        ForkResult::Child => {
+            drop(write_end);
+            // Wait until the parent allows us to ptrace it, it closes the pipe
+            // without writing if it can't.
+            let mut ready = [0u8];
+            let status = match nix::unistd::read(read_end.as_raw_fd(), &mut ready) {
+                Ok(1) => match writer.dump(destination) {
+                    Ok(_) => 0,
+                    Err(e) => {
+                        log::error!("failed to write the dump of the parent process: {e}");
+                        1
+                    }
+                },
+                _ => 2,
+            };
+            // Safety: exit without running the destructors and `atexit`
+            // handlers of the parent.
+            unsafe { libc::_exit(status) }
+        }
+        ForkResult::Parent { child } => {
+            drop(read_end);
+            let allowed = set_ptracer(child.as_raw());
+            if allowed.is_ok() {
+                // If this fails the child exits and the dump fails.
+                let _ = nix::unistd::write(&write_end, &[1]);
+            }
+            drop(write_end);
+
+            let status = loop {
+                match waitpid(child, None) {
+                    Err(nix::Error::EINTR) => continue,
+                    status => break status,
+                }
+            };
+            let _ = set_ptracer(0);
+            allowed?;
+
+            match status.map_err(CurrentProcessDumpError::WaitFailed)? {
+                WaitStatus::Exited(_, 0) => Ok(()),
+                WaitStatus::Exited(_, code) => Err(CurrentProcessDumpError::DumpFailed(code)),
+                _ => Err(CurrentProcessDumpError::DumperKilled),
+            }
+        }
+    }
+}
+
+/// Allow `pid` to ptrace the current process if Yama restricts ptrace to
+/// descendants. 0 revokes it.
+fn set_ptracer(pid: Pid) -> Result<(), CurrentProcessDumpError> {
+    // Safety: PR_SET_PTRACER takes a pid and no pointers.
+    let res = unsafe { libc::prctl(libc::PR_SET_PTRACER, pid as libc::c_ulong, 0, 0, 0) };
+    match nix::errno::Errno::result(res) {
+        // Yama isn't enabled, anyone may ptrace us already.
+        Ok(_) | Err(nix::Error::EINVAL) => Ok(()),
+        Err(e) => Err(CurrentProcessDumpError::SetPtracerFailed(e)),
+    }
+}
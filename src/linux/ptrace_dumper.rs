@@ -10,9 +10,11 @@ use {
             errors::{DumperError, ThreadInfoError},
             maps_reader::MappingInfo,
             module_reader,
+            sanitization::{self, SanitizationPolicy, SanitizationStats},
             thread_info::ThreadInfo,
             Pid,
         },
+        minidump_cpu::RawContextCPU,
         serializers::*,
     },
     error_graph::{ErrorList, WriteErrorList},
@@ -42,6 +44,9 @@ use crate::thread_info;
 #[derive(Debug, Clone)]
 pub struct Thread {
     pub tid: Pid,
+    /// The id of the thread in the pid namespace of the process, if it differs
+    /// from `tid`
+    pub ns_tid: Option<Pid>,
     pub name: Option<String>,
 }
 
@@ -427,7 +432,22 @@ impl PtraceDumper {
                 }
             };
 
-            self.threads.push(Thread { tid, name });
+            // The last entry of NSpid is the id in the innermost namespace.
+            // This is best-effort, as it is only reported if requested.
+            let ns_tid = std::fs::read_to_string(format!("/proc/{pid}/task/{tid}/status"))
+                .ok()
+                .and_then(|status| {
+                    status
+                        .lines()
+                        .find_map(|line| line.strip_prefix("NSpid:"))?
+                        .split_whitespace()
+                        .next_back()?
+                        .parse::<Pid>()
+                        .ok()
+                })
+                .filter(|ns_tid| *ns_tid != tid);
+
+            self.threads.push(Thread { tid, ns_tid, name });
         }
 
         Ok(())
@@ -535,29 +555,98 @@ impl PtraceDumper {
         false
     }
 
+    /// Return the guard below a stack mapping that `address` is in, if any: an
+    /// inaccessible mapping or unmapped gap that ends where the stack mapping
+    /// starts, at most 1 MiB above `address`. This is where the stack pointer,
+    /// or the address of the fault, is after a stack overflow.
+    pub fn find_stack_guard(&self, address: usize) -> Option<std::ops::Range<usize>> {
+        if Self::may_be_stack(self.find_mapping(address)) {
+            return None;
+        }
+        // See `get_stack_info` for the size of the guard page
+        let guard_page_max_addr = address.saturating_add(1024 * 1024);
+
+        let stack = self
+            .mappings
+            .iter()
+            .filter(|mapping| mapping.start_address > address)
+            .min_by_key(|mapping| mapping.start_address)?;
+        if !Self::may_be_stack(Some(stack)) || stack.start_address > guard_page_max_addr {
+            return None;
+        }
+
+        let guard_start = match self.find_mapping(address) {
+            Some(guard) => guard.start_address,
+            None => self
+                .mappings
+                .iter()
+                .map(MappingInfo::end_address)
+                .filter(|end| *end <= address)
+                .max()
+                .unwrap_or_default(),
+        };
+        Some(guard_start..stack.start_address)
+    }
+
     pub fn sanitize_stack_copy(
         &self,
         stack_copy: &mut [u8],
         stack_pointer: usize,
         sp_offset: usize,
     ) -> Result<(), DumperError> {
+        self.sanitize_memory(
+            &SanitizationPolicy::default(),
+            stack_copy,
+            stack_pointer,
+            sp_offset,
+        )
+        .map(|_| ())
+    }
+
+    /// Scrub the general purpose and vector registers in `cpu` that `policy`
+    /// doesn't keep, see [`SanitizationPolicy::sanitize_registers`]. Pointers
+    /// into the stack containing `stack_pointer` are kept.
+    pub fn sanitize_cpu_context(
+        &self,
+        policy: &SanitizationPolicy,
+        cpu: &mut RawContextCPU,
+        stack_pointer: usize,
+    ) {
+        let stack_mapping = self.find_mapping_no_bias(stack_pointer);
+        sanitization::for_each_register_word(cpu, |word| {
+            let value = usize::from_ne_bytes(word.try_into().expect("word sized chunk"));
+            let keep = policy.is_small_int(value)
+                || stack_mapping.is_some_and(|stack| stack.contains_address(value))
+                || policy.is_allowed(value)
+                || self
+                    .find_mapping_no_bias(value)
+                    .is_some_and(|mapping| mapping.is_executable());
+            if !keep {
+                policy.scrub(word);
+            }
+        });
+    }
+
+    /// Scrub the words of `memory` that `policy` doesn't keep. Pointers into
+    /// the mapping containing `address` are kept, and the first `sp_offset`
+    /// bytes are zeroed, so for stacks `address` is the stack pointer and
+    /// `sp_offset` its offset in `memory`.
+    pub fn sanitize_memory(
+        &self,
+        policy: &SanitizationPolicy,
+        memory: &mut [u8],
+        address: usize,
+        sp_offset: usize,
+    ) -> Result<SanitizationStats, DumperError> {
         // We optimize the search for containing mappings in three ways:
-        // 1) We expect that pointers into the stack mapping will be common, so
-        //    we cache that address range.
+        // 1) We expect that pointers into the own mapping (e.g. the stack) will
+        //    be common, so we cache that address range.
         // 2) The last referenced mapping is a reasonable predictor for the next
         //    referenced mapping, so we test that first.
         // 3) We precompute a bitfield based upon bits 32:32-n of the start and
         //    stop addresses, and use that to short circuit any values that can
         //    not be pointers. (n=11)
-        let defaced;
-        #[cfg(target_pointer_width = "64")]
-        {
-            defaced = 0x0defaced0defacedusize.to_ne_bytes();
-        }
-        #[cfg(target_pointer_width = "32")]
-        {
-            defaced = 0x0defacedusize.to_ne_bytes();
-        };
+        //
         // the bitfield length is 2^test_bits long.
         let test_bits = 11;
         // byte length of the corresponding array.
@@ -567,14 +656,9 @@ impl PtraceDumper {
         // on 32 bit architectures. On 64 bit architectures this would be
         // uninformative so we take the same range of bits.
         let shift = 32 - 11;
-        // let MappingInfo* last_hit_mapping = nullptr;
-        // let MappingInfo* hit_mapping = nullptr;
-        let stack_mapping = self.find_mapping_no_bias(stack_pointer);
+        let own_mapping = self.find_mapping_no_bias(address);
         let mut last_hit_mapping: Option<&MappingInfo> = None;
-        // The magnitude below which integers are considered to be to be
-        // 'small', and not constitute a PII risk. These are included to
-        // avoid eliding useful register values.
-        let small_int_magnitude: isize = 4096;
+        let mut stats = SanitizationStats::default();
 
         let mut could_hit_mapping = vec![0; array_size];
         // Initialize the bitfield such that if the (pointer >> shift)'th
@@ -597,32 +681,29 @@ impl PtraceDumper {
         }
 
         // Zero memory that is below the current stack pointer.
-        let offset =
-            (sp_offset + std::mem::size_of::<usize>() - 1) & !(std::mem::size_of::<usize>() - 1);
-        for x in &mut stack_copy[0..offset] {
-            *x = 0;
-        }
-        let mut chunks = stack_copy[offset..].chunks_exact_mut(std::mem::size_of::<usize>());
+        let offset = ((sp_offset + std::mem::size_of::<usize>() - 1)
+            & !(std::mem::size_of::<usize>() - 1))
+            .min(memory.len());
+        memory[0..offset].fill(0);
+        stats.zeroed_bytes += offset as u64;
+        let mut chunks = memory[offset..].chunks_exact_mut(std::mem::size_of::<usize>());
 
         // Apply sanitization to each complete pointer-aligned word in the
-        // stack.
+        // memory.
         for sp in &mut chunks {
-            let addr = usize::from_ne_bytes(sp.to_vec().as_slice().try_into()?);
-            let addr_signed = isize::from_ne_bytes(sp.to_vec().as_slice().try_into()?);
+            let addr = usize::from_ne_bytes((&*sp).try_into()?);
 
-            if addr <= small_int_magnitude as usize && addr_signed >= -small_int_magnitude {
+            if policy.is_small_int(addr) {
+                stats.kept_small_ints += 1;
                 continue;
             }
 
-            if let Some(stack_map) = stack_mapping {
-                if stack_map.contains_address(addr) {
-                    continue;
-                }
-            }
-            if let Some(last_hit) = last_hit_mapping {
-                if last_hit.contains_address(addr) {
-                    continue;
-                }
+            if own_mapping.is_some_and(|own_map| own_map.contains_address(addr))
+                || last_hit_mapping.is_some_and(|last_hit| last_hit.contains_address(addr))
+                || policy.is_allowed(addr)
+            {
+                stats.kept_pointers += 1;
+                continue;
             }
 
             let test = addr >> shift;
@@ -630,18 +711,20 @@ impl PtraceDumper {
                 if let Some(hit_mapping) = self.find_mapping_no_bias(addr) {
                     if hit_mapping.is_executable() {
                         last_hit_mapping = Some(hit_mapping);
+                        stats.kept_pointers += 1;
                         continue;
                     }
                 }
             }
-            sp.copy_from_slice(&defaced);
+            policy.scrub(sp);
+            stats.scrubbed_words += 1;
         }
-        // Zero any partial word at the top of the stack, if alignment is
+        // Zero any partial word at the end of the memory, if alignment is
         // such that that is required.
-        for sp in chunks.into_remainder() {
-            *sp = 0;
-        }
-        Ok(())
+        let remainder = chunks.into_remainder();
+        stats.zeroed_bytes += remainder.len() as u64;
+        remainder.fill(0);
+        Ok(stats)
     }
 
     // Find the mapping which the given memory address falls in.
@@ -673,9 +756,16 @@ impl PtraceDumper {
     pub fn from_process_memory_for_mapping<T: module_reader::ReadFromModule>(
         mapping: &MappingInfo,
         pid: Pid,
+    ) -> Result<T, DumperError> {
+        Self::from_process_memory_for_address(mapping.start_address, pid)
+    }
+
+    pub fn from_process_memory_for_address<T: module_reader::ReadFromModule>(
+        start_address: usize,
+        pid: Pid,
     ) -> Result<T, DumperError> {
         Ok(T::read_from_module(
-            module_reader::ProcessReader::new(pid, mapping.start_address).into(),
+            module_reader::ProcessReader::new(pid, start_address).into(),
         )?)
     }
 }
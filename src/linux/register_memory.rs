@@ -0,0 +1,182 @@
+//! Memory the registers of a thread point to, see [`RegisterMemoryPolicy`].
+
+use crate::minidump_cpu::RawContextCPU;
+use std::ops::Range;
+
+/// The kinds of values memory windows are captured around.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegisterClass {
+    /// The program counter
+    InstructionPointer,
+    /// The stack pointer and the frame pointer
+    StackPointer,
+    /// All other general purpose registers
+    GeneralPurpose,
+    /// The address of the fault from the `siginfo` of the crash
+    FaultAddress,
+}
+
+/// The windows of memory captured around the register values of the crashing
+/// thread that point into readable memory. Every window is centered on the
+/// value and bounded by the mapping it is in. A window size of 0 captures
+/// nothing for that class.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RegisterMemoryPolicy {
+    pub instruction_pointer_window: usize,
+    pub stack_pointer_window: usize,
+    pub general_purpose_window: usize,
+    pub fault_address_window: usize,
+    /// Capture windows around the registers of every thread, not just the
+    /// crashing one.
+    pub all_threads: bool,
+}
+
+impl Default for RegisterMemoryPolicy {
+    fn default() -> Self {
+        Self {
+            instruction_pointer_window: 256,
+            // The stacks are captured anyway
+            stack_pointer_window: 0,
+            general_purpose_window: 256,
+            fault_address_window: 256,
+            all_threads: false,
+        }
+    }
+}
+
+impl RegisterMemoryPolicy {
+    pub fn window_size(&self, class: RegisterClass) -> usize {
+        match class {
+            RegisterClass::InstructionPointer => self.instruction_pointer_window,
+            RegisterClass::StackPointer => self.stack_pointer_window,
+            RegisterClass::GeneralPurpose => self.general_purpose_window,
+            RegisterClass::FaultAddress => self.fault_address_window,
+        }
+    }
+
+    /// The window around `value`, not bounded by any mapping yet.
+    pub(crate) fn window(&self, class: RegisterClass, value: usize) -> Range<usize> {
+        let size = self.window_size(class);
+        let start = value.saturating_sub(size / 2);
+        start..start.saturating_add(size)
+    }
+}
+
+/// The values of the general purpose registers in `cpu`, by class.
+pub(crate) fn register_values(cpu: &RawContextCPU) -> Vec<(RegisterClass, usize)> {
+    let mut values = Vec::new();
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            values.push((RegisterClass::InstructionPointer, cpu.rip as usize));
+            for reg in [cpu.rsp, cpu.rbp] {
+                values.push((RegisterClass::StackPointer, reg as usize));
+            }
+            for reg in [
+                cpu.rax, cpu.rbx, cpu.rcx, cpu.rdx, cpu.rsi, cpu.rdi, cpu.r8, cpu.r9, cpu.r10,
+                cpu.r11, cpu.r12, cpu.r13, cpu.r14, cpu.r15,
+            ] {
+                values.push((RegisterClass::GeneralPurpose, reg as usize));
+            }
+        } else if #[cfg(target_arch = "x86")] {
+            values.push((RegisterClass::InstructionPointer, cpu.eip as usize));
+            for reg in [cpu.esp, cpu.ebp] {
+                values.push((RegisterClass::StackPointer, reg as usize));
+            }
+            for reg in [cpu.eax, cpu.ebx, cpu.ecx, cpu.edx, cpu.esi, cpu.edi] {
+                values.push((RegisterClass::GeneralPurpose, reg as usize));
+            }
+        } else if #[cfg(target_arch = "arm")] {
+            values.push((RegisterClass::InstructionPointer, cpu.iregs[15] as usize));
+            // sp (r13) and fp (r11)
+            for idx in [13, 11] {
+                values.push((RegisterClass::StackPointer, cpu.iregs[idx] as usize));
+            }
+            for idx in (0..=10).chain([12, 14]) {
+                values.push((RegisterClass::GeneralPurpose, cpu.iregs[idx] as usize));
+            }
+        } else if #[cfg(target_arch = "aarch64")] {
+            values.push((RegisterClass::InstructionPointer, cpu.pc as usize));
+            // sp and fp (x29)
+            for reg in [cpu.sp, cpu.iregs[29]] {
+                values.push((RegisterClass::StackPointer, reg as usize));
+            }
+            for idx in (0..=28).chain([30]) {
+                values.push((RegisterClass::GeneralPurpose, cpu.iregs[idx] as usize));
+            }
+        }
+    }
+    values
+}
+
+/// Sort `ranges` and merge the overlapping and adjacent ones, then remove the
+/// parts that are already in `covered`.
+pub(crate) fn merge_and_subtract(
+    mut ranges: Vec<Range<usize>>,
+    covered: &[Range<usize>],
+) -> Vec<Range<usize>> {
+    ranges.retain(|range| !range.is_empty());
+    ranges.sort_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::new();
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+
+    let mut result = Vec::new();
+    for mut range in merged {
+        let mut overlapping: Vec<&Range<usize>> = covered
+            .iter()
+            .filter(|covered| covered.start < range.end && range.start < covered.end)
+            .collect();
+        overlapping.sort_by_key(|covered| covered.start);
+        for covered in overlapping {
+            if covered.start > range.start {
+                result.push(range.start..covered.start);
+            }
+            range.start = range.start.max(covered.end);
+        }
+        if range.start < range.end {
+            result.push(range);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_and_subtract() {
+        assert_eq!(
+            merge_and_subtract(vec![0x300..0x400, 0x100..0x200, 0x180..0x280], &[]),
+            [0x100..0x280, 0x300..0x400]
+        );
+        assert_eq!(
+            merge_and_subtract(
+                vec![0x100..0x400, 0x500..0x600],
+                &[0x180..0x200, 0x300..0x480, 0x500..0x600]
+            ),
+            [0x100..0x180, 0x200..0x300]
+        );
+        assert_eq!(
+            merge_and_subtract(vec![0x100..0x100, 0x200..0x200], &[]),
+            []
+        );
+    }
+
+    #[test]
+    fn test_window() {
+        let policy = RegisterMemoryPolicy::default();
+        assert_eq!(
+            policy.window(RegisterClass::GeneralPurpose, 0x1000),
+            0xf80..0x1080
+        );
+        assert_eq!(policy.window(RegisterClass::GeneralPurpose, 0x10), 0..0x100);
+        assert!(policy
+            .window(RegisterClass::StackPointer, 0x1000)
+            .is_empty());
+    }
+}
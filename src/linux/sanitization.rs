@@ -0,0 +1,247 @@
+//! Scrubbing of memory that may contain personal data before it is written to
+//! the minidump, see [`SanitizationPolicy`].
+
+use crate::minidump_cpu::RawContextCPU;
+use std::ops::Range;
+
+/// The value scrubbed words are overwritten with in [`ScrubMode::Mark`].
+#[cfg(target_pointer_width = "64")]
+pub const SCRUB_MARKER: usize = 0x0defaced0defaced;
+#[cfg(target_pointer_width = "32")]
+pub const SCRUB_MARKER: usize = 0x0defaced;
+
+/// How scrubbed words are overwritten.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ScrubMode {
+    /// With [`SCRUB_MARKER`], so that scrubbed words can be told apart from
+    /// zeroes.
+    #[default]
+    Mark,
+    /// With zeroes.
+    Zero,
+}
+
+/// What is kept when sanitizing memory.
+///
+/// Memory is sanitized one pointer-sized word at a time. Words are kept if
+/// they are small integers, or pointers into executable mappings, into the
+/// mapping of the memory itself (e.g. the stack), or into one of the allowed
+/// ranges. All other words are scrubbed. Memory below the stack pointer and
+/// partial words at the end are always zeroed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SanitizationPolicy {
+    pub scrub_mode: ScrubMode,
+    /// Words whose value is at most this are kept, as they are useful
+    /// register values rather than personal data.
+    pub small_int_magnitude: usize,
+    /// Keep words whose magnitude as a negative integer is at most
+    /// `small_int_magnitude` as well.
+    pub keep_small_negative_ints: bool,
+    /// Address ranges pointers into which are kept, e.g. the heap arenas of
+    /// the application.
+    pub allowed_ranges: Vec<Range<usize>>,
+    /// Sanitize the application provided memory regions as well.
+    pub sanitize_app_memory: bool,
+    /// Sanitize the memory around the instruction pointer of the crashing
+    /// thread as well.
+    pub sanitize_ip_memory: bool,
+    /// Sanitize the general purpose and vector registers of every thread as
+    /// well. The program counter, stack pointer and frame pointer are kept, so
+    /// that stacks can still be unwound.
+    pub sanitize_registers: bool,
+}
+
+impl Default for SanitizationPolicy {
+    fn default() -> Self {
+        Self {
+            scrub_mode: ScrubMode::default(),
+            small_int_magnitude: 4096,
+            keep_small_negative_ints: false,
+            allowed_ranges: Vec::new(),
+            sanitize_app_memory: false,
+            sanitize_ip_memory: false,
+            sanitize_registers: false,
+        }
+    }
+}
+
+impl SanitizationPolicy {
+    /// Keep pointers into `range`.
+    pub fn allow_range(&mut self, range: Range<usize>) -> &mut Self {
+        self.allowed_ranges.push(range);
+        self
+    }
+
+    pub(crate) fn is_allowed(&self, address: usize) -> bool {
+        self.allowed_ranges
+            .iter()
+            .any(|range| range.contains(&address))
+    }
+
+    pub(crate) fn is_small_int(&self, word: usize) -> bool {
+        word <= self.small_int_magnitude
+            || (self.keep_small_negative_ints && word.wrapping_neg() <= self.small_int_magnitude)
+    }
+
+    pub(crate) fn scrub(&self, word: &mut [u8]) {
+        match self.scrub_mode {
+            ScrubMode::Mark => word.copy_from_slice(&SCRUB_MARKER.to_ne_bytes()),
+            ScrubMode::Zero => word.fill(0),
+        }
+    }
+}
+
+/// A register whose value can be sanitized one pointer-sized word at a time.
+trait Register {
+    fn for_each_word(&mut self, f: &mut impl FnMut(&mut [u8]));
+}
+
+macro_rules! impl_register {
+    ($($ty:ty),*) => {
+        $(
+            impl Register for $ty {
+                fn for_each_word(&mut self, f: &mut impl FnMut(&mut [u8])) {
+                    let mut bytes = self.to_ne_bytes();
+                    bytes
+                        .chunks_exact_mut(std::mem::size_of::<usize>())
+                        .for_each(&mut *f);
+                    *self = <$ty>::from_ne_bytes(bytes);
+                }
+            }
+        )*
+    };
+}
+
+impl_register!(u32, u64, u128);
+
+impl Register for [u8] {
+    fn for_each_word(&mut self, f: &mut impl FnMut(&mut [u8])) {
+        self.chunks_exact_mut(std::mem::size_of::<usize>())
+            .for_each(&mut *f);
+    }
+}
+
+/// Call `f` with every pointer-sized word of the general purpose and vector
+/// registers in `cpu`, except for the program counter, stack pointer and frame
+/// pointer.
+pub(crate) fn for_each_register_word(cpu: &mut RawContextCPU, mut f: impl FnMut(&mut [u8])) {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            use scroll::{Pread, Pwrite};
+
+            for reg in [
+                &mut cpu.rax,
+                &mut cpu.rbx,
+                &mut cpu.rcx,
+                &mut cpu.rdx,
+                &mut cpu.rsi,
+                &mut cpu.rdi,
+                &mut cpu.r8,
+                &mut cpu.r9,
+                &mut cpu.r10,
+                &mut cpu.r11,
+                &mut cpu.r12,
+                &mut cpu.r13,
+                &mut cpu.r14,
+                &mut cpu.r15,
+            ] {
+                reg.for_each_word(&mut f);
+            }
+
+            let mut float_save: crate::minidump_cpu::FloatStateCPU = cpu
+                .float_save
+                .pread_with(0, scroll::Endian::Little)
+                .expect("this is impossible");
+            for reg in float_save
+                .float_registers
+                .iter_mut()
+                .chain(&mut float_save.xmm_registers)
+            {
+                reg.for_each_word(&mut f);
+            }
+            cpu.float_save
+                .pwrite_with(float_save, 0, scroll::Endian::Little)
+                .expect("this is impossible");
+        } else if #[cfg(target_arch = "x86")] {
+            for reg in [
+                &mut cpu.eax,
+                &mut cpu.ebx,
+                &mut cpu.ecx,
+                &mut cpu.edx,
+                &mut cpu.esi,
+                &mut cpu.edi,
+            ] {
+                reg.for_each_word(&mut f);
+            }
+            cpu.float_save.register_area.for_each_word(&mut f);
+            // The x87 and XMM registers in the fxsave area
+            cpu.extended_registers[32..416].for_each_word(&mut f);
+        } else if #[cfg(target_arch = "arm")] {
+            // r0-r10, ip and lr, but not fp (r11), sp (r13) and pc (r15)
+            for idx in (0..=10).chain([12, 14]) {
+                cpu.iregs[idx].for_each_word(&mut f);
+            }
+            for reg in &mut cpu.float_save.regs {
+                reg.for_each_word(&mut f);
+            }
+        } else if #[cfg(target_arch = "aarch64")] {
+            // x0-x28 and lr, but not fp (x29)
+            for idx in (0..=28).chain([30]) {
+                cpu.iregs[idx].for_each_word(&mut f);
+            }
+            for reg in &mut cpu.float_regs {
+                reg.for_each_word(&mut f);
+            }
+        }
+    }
+}
+
+/// What sanitizing a region of memory did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SanitizationStats {
+    /// Words kept because they are small integers
+    pub kept_small_ints: u64,
+    /// Words kept because they are pointers to memory that is kept
+    pub kept_pointers: u64,
+    /// Words that were scrubbed
+    pub scrubbed_words: u64,
+    /// Bytes below the stack pointer, or in a partial word, that were zeroed
+    pub zeroed_bytes: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_ints() {
+        let mut policy = SanitizationPolicy::default();
+        assert!(policy.is_small_int(0));
+        assert!(policy.is_small_int(4096));
+        assert!(!policy.is_small_int(4097));
+        assert!(!policy.is_small_int(-1isize as usize));
+        assert!(!policy.is_small_int(usize::MAX / 2));
+
+        policy.keep_small_negative_ints = true;
+        assert!(policy.is_small_int(4096));
+        assert!(policy.is_small_int(-4096isize as usize));
+        assert!(!policy.is_small_int(-4097isize as usize));
+        assert!(!policy.is_small_int(usize::MAX / 2));
+    }
+
+    #[test]
+    fn test_scrub() {
+        let mut policy = SanitizationPolicy::default();
+        let mut word = 42usize.to_ne_bytes();
+        policy.scrub(&mut word);
+        assert_eq!(usize::from_ne_bytes(word), SCRUB_MARKER);
+
+        policy.scrub_mode = ScrubMode::Zero;
+        policy.scrub(&mut word);
+        assert_eq!(usize::from_ne_bytes(word), 0);
+
+        policy.allow_range(0x1000..0x2000);
+        assert!(policy.is_allowed(0x1000));
+        assert!(!policy.is_allowed(0x2000));
+    }
+}
@@ -1,9 +1,22 @@
 pub mod app_memory;
+pub mod container_info_stream;
+pub mod data_segments;
+pub mod dump_reason_stream;
 pub mod exception_stream;
 pub mod handle_data_stream;
+pub mod jit_symbols_stream;
 pub mod mappings;
 pub mod memory_info_list_stream;
 pub mod memory_list_stream;
+pub mod memory_rules;
+pub mod module_info_stream;
+pub mod module_metadata;
+pub mod rust_panic_stream;
+pub mod sanitization_stats_stream;
+pub mod security_context_stream;
+pub mod signal_frames_stream;
+pub mod smaps_stream;
+pub mod stack_overflow_stream;
 pub mod systeminfo_stream;
 pub mod thread_list_stream;
 pub mod thread_names_stream;
@@ -1,16 +1,28 @@
 use super::*;
+use crate::linux::ext_streams::SanitizedRegionKind;
 
 /// Write application-provided memory regions.
 pub fn write(
     config: &mut MinidumpWriter,
     buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
 ) -> Result<(), errors::SectionAppMemoryError> {
-    for app_memory in &config.app_memory {
-        let data_copy = PtraceDumper::copy_from_process(
+    for app_memory in config.app_memory.clone() {
+        let mut data_copy = PtraceDumper::copy_from_process(
             config.blamed_thread,
             app_memory.ptr,
             app_memory.length,
         )?;
+        if config.sanitize_stack && config.sanitization_policy.sanitize_app_memory {
+            config.sanitize_memory(
+                dumper,
+                SanitizedRegionKind::AppMemory,
+                &mut data_copy,
+                app_memory.ptr,
+                app_memory.ptr,
+                0,
+            )?;
+        }
 
         let section = MemoryArrayWriter::write_bytes(buffer, &data_copy);
         let desc = MDMemoryDescriptor {
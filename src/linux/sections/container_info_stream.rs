@@ -0,0 +1,382 @@
+use super::*;
+use crate::linux::{
+    ext_streams::{ExtStreamType, MDRawCgroupEntry, MDRawContainerInfo},
+    Pid,
+};
+use procfs_core::{
+    process::{MountInfo, MountInfos, Status},
+    FromRead, ProcessCGroup, ProcessCGroups,
+};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// cgroups v1 reports an unlimited memory limit as the largest page aligned
+/// value of an `i64`, anything above this is considered unlimited.
+const CGROUP_V1_UNLIMITED: u64 = i64::MAX as u64 & !0xffff;
+
+/// Write a LinuxContainerInfo stream with the cgroups and namespaces of the
+/// process, and the runtime it appears to be running in.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionContainerInfoError> {
+    let pid = config.process_id;
+    let cgroups = ProcessCGroups::from_file(format!("/proc/{pid}/cgroup"))?.0;
+    let status = Status::from_file(format!("/proc/{pid}/status"))?;
+
+    let mut info = MDRawContainerInfo {
+        size_of_header: std::mem::size_of::<MDRawContainerInfo>() as u32,
+        size_of_cgroup: std::mem::size_of::<MDRawCgroupEntry>() as u32,
+        number_of_cgroups: cgroups.len() as u32,
+        ..Default::default()
+    };
+
+    // NSpid lists the pid in every namespace, from the outermost one
+    // visible to us to the namespace of the process.
+    let nspid = status.nspid.unwrap_or_default();
+    info.ns_pid = nspid.last().copied().unwrap_or(pid) as u32;
+    info.pid_ns_depth = nspid.len().max(1) as u32;
+
+    for (name, id) in [
+        ("cgroup", &mut info.cgroup_ns),
+        ("ipc", &mut info.ipc_ns),
+        ("mnt", &mut info.mnt_ns),
+        ("net", &mut info.net_ns),
+        ("pid", &mut info.pid_ns),
+        ("time", &mut info.time_ns),
+        ("user", &mut info.user_ns),
+        ("uts", &mut info.uts_ns),
+    ] {
+        *id = namespace_id(pid, name).unwrap_or_default();
+    }
+
+    // The cgroup filesystems are read where they are mounted in the mount
+    // namespace of the process, which may not be ours
+    let root = PathBuf::from(format!("/proc/{pid}/root"));
+    let mounts = MountInfos::from_file(format!("/proc/{pid}/mountinfo"))
+        .map(|mounts| mounts.0)
+        .unwrap_or_default();
+    for cgroup in &cgroups {
+        if let Some(dir) = cgroup_dir(&root, &mounts, cgroup) {
+            read_limits(cgroup, &dir, &mut info);
+        }
+    }
+
+    let mut hints = runtime_hints(
+        &fs::read(format!("/proc/{pid}/environ")).unwrap_or_default(),
+        &cgroups,
+    );
+    for (file, runtime) in [(".dockerenv", "docker"), ("run/.containerenv", "podman")] {
+        if !hints.contains(&runtime) && root.join(file).exists() {
+            hints.push(runtime);
+        }
+    }
+    if !hints.is_empty() {
+        info.runtime_hints_rva = write_string_to_location(buffer, &hints.join(","))?.rva;
+    }
+
+    let entries = cgroups
+        .iter()
+        .map(|cgroup| {
+            Ok(MDRawCgroupEntry {
+                hierarchy: cgroup.hierarchy,
+                controllers_rva: write_string_to_location(buffer, &cgroup.controllers.join(","))?
+                    .rva,
+                path_rva: write_string_to_location(buffer, &cgroup.pathname)?.rva,
+                __alignment: 0,
+            })
+        })
+        .collect::<Result<Vec<_>, MemoryWriterError>>()?;
+
+    let header = MemoryWriter::alloc_with_val(buffer, info)?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: ExtStreamType::LinuxContainerInfo.into(),
+        location: header.location(),
+    };
+
+    let entries = MemoryArrayWriter::<MDRawCgroupEntry>::alloc_from_iter(buffer, entries)?;
+
+    dirent.location.data_size += entries.location().data_size;
+
+    Ok(dirent)
+}
+
+/// The inode of the namespace link `/proc/$pid/ns/$name`, which reads like
+/// `net:[4026531840]`.
+fn namespace_id(pid: Pid, name: &str) -> Option<u64> {
+    let target = fs::read_link(format!("/proc/{pid}/ns/{name}")).ok()?;
+    target
+        .to_str()?
+        .strip_prefix(name)?
+        .strip_prefix(":[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// The directory of `cgroup` below `root`, the root directory of the process,
+/// from the cgroup filesystems in its `mounts`.
+///
+/// Both the cgroup paths and the roots of the mounts are relative to the root
+/// of our cgroup namespace, so a mount only holds the cgroup if its root is a
+/// prefix of the cgroup path.
+fn cgroup_dir(root: &Path, mounts: &[MountInfo], cgroup: &ProcessCGroup) -> Option<PathBuf> {
+    mounts
+        .iter()
+        .filter(|mount| {
+            if cgroup.hierarchy == 0 {
+                return mount.fs_type == "cgroup2";
+            }
+            // The super options of a v1 hierarchy list its controllers, or
+            // its name for named hierarchies
+            mount.fs_type == "cgroup"
+                && cgroup
+                    .controllers
+                    .iter()
+                    .all(|controller| match controller.split_once('=') {
+                        Some((key, value)) => {
+                            mount.super_options.get(key) == Some(&Some(value.to_owned()))
+                        }
+                        None => mount.super_options.contains_key(controller),
+                    })
+        })
+        .find_map(|mount| {
+            let relative = Path::new(&cgroup.pathname).strip_prefix(&mount.root).ok()?;
+            let mount_point = mount.mount_point.strip_prefix("/").ok()?;
+            Some(root.join(mount_point).join(relative))
+        })
+}
+
+fn read_value(dir: &Path, file: &str) -> Option<String> {
+    fs::read_to_string(dir.join(file))
+        .ok()
+        .map(|value| value.trim().to_owned())
+}
+
+/// Parse a v2 limit, which is either a number or `max`.
+fn parse_limit(value: &str) -> Option<u64> {
+    if value == "max" {
+        Some(u64::MAX)
+    } else {
+        value.parse().ok()
+    }
+}
+
+/// Fill in the resource limits and usage of `info` that are known by the
+/// cgroup in `dir`. Values that were already found are kept, as on hybrid
+/// systems the limits are spread over multiple hierarchies.
+fn read_limits(cgroup: &ProcessCGroup, dir: &Path, info: &mut MDRawContainerInfo) {
+    fn set(field: &mut u64, value: Option<u64>) {
+        if *field == 0 {
+            *field = value.unwrap_or_default();
+        }
+    }
+
+    if cgroup.hierarchy == 0 {
+        set(
+            &mut info.memory_limit,
+            read_value(dir, "memory.max").and_then(|v| parse_limit(&v)),
+        );
+        set(
+            &mut info.memory_usage,
+            read_value(dir, "memory.current").and_then(|v| v.parse().ok()),
+        );
+        if let Some(cpu_max) = read_value(dir, "cpu.max") {
+            let mut fields = cpu_max.split_whitespace();
+            set(&mut info.cpu_quota_us, fields.next().and_then(parse_limit));
+            set(
+                &mut info.cpu_period_us,
+                fields.next().and_then(|v| v.parse().ok()),
+            );
+        }
+        if let Some(cpu_stat) = read_value(dir, "cpu.stat") {
+            set(
+                &mut info.cpu_usage_us,
+                cpu_stat
+                    .lines()
+                    .find_map(|line| line.strip_prefix("usage_usec "))
+                    .and_then(|v| v.parse().ok()),
+            );
+        }
+        return;
+    }
+
+    let has_controller = |name: &str| cgroup.controllers.iter().any(|c| c == name);
+    if has_controller("memory") {
+        set(
+            &mut info.memory_limit,
+            read_value(dir, "memory.limit_in_bytes")
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(|limit| {
+                    if limit >= CGROUP_V1_UNLIMITED {
+                        u64::MAX
+                    } else {
+                        limit
+                    }
+                }),
+        );
+        set(
+            &mut info.memory_usage,
+            read_value(dir, "memory.usage_in_bytes").and_then(|v| v.parse().ok()),
+        );
+    }
+    if has_controller("cpu") {
+        set(
+            &mut info.cpu_quota_us,
+            read_value(dir, "cpu.cfs_quota_us")
+                .and_then(|v| v.parse::<i64>().ok())
+                .map(|quota| u64::try_from(quota).unwrap_or(u64::MAX)),
+        );
+        set(
+            &mut info.cpu_period_us,
+            read_value(dir, "cpu.cfs_period_us").and_then(|v| v.parse().ok()),
+        );
+    }
+    if has_controller("cpuacct") {
+        set(
+            &mut info.cpu_usage_us,
+            read_value(dir, "cpuacct.usage")
+                .and_then(|v| v.parse::<u64>().ok())
+                .map(|ns| ns / 1000),
+        );
+    }
+}
+
+/// Guess the container runtimes from the environment and the cgroup paths of
+/// the process.
+fn runtime_hints(environ: &[u8], cgroups: &[ProcessCGroup]) -> Vec<&'static str> {
+    let mut hints = Vec::new();
+    let mut add = |hint: &'static str| {
+        if !hints.contains(&hint) {
+            hints.push(hint);
+        }
+    };
+
+    for var in environ.split(|b| *b == 0) {
+        if var.starts_with(b"KUBERNETES_SERVICE_HOST=") {
+            add("kubernetes");
+        } else if let Some(runtime) = var.strip_prefix(b"container=") {
+            // Set by podman, lxc and systemd-nspawn
+            match runtime {
+                b"docker" => add("docker"),
+                b"podman" | b"oci" => add("podman"),
+                b"lxc" | b"lxc-libvirt" => add("lxc"),
+                b"systemd-nspawn" => add("systemd-nspawn"),
+                _ => {}
+            }
+        }
+    }
+
+    for cgroup in cgroups {
+        let path = &cgroup.pathname;
+        if path.contains("kubepods") {
+            add("kubernetes");
+        }
+        if path.contains("docker") {
+            add("docker");
+        }
+        if path.contains("libpod") {
+            add("podman");
+        }
+        if path.contains("containerd") {
+            add("containerd");
+        }
+        if path.contains("/lxc") || path.contains("lxc.payload") {
+            add("lxc");
+        }
+        if path.contains("systemd-nspawn") {
+            add("systemd-nspawn");
+        }
+    }
+
+    hints
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_runtime_hints() {
+        let cgroups = ProcessCGroups::from_read(
+            "0::/kubepods.slice/kubepods-burstable.slice/cri-containerd-0123abcd.scope\n"
+                .as_bytes(),
+        )
+        .unwrap();
+        assert_eq!(
+            runtime_hints(b"PATH=/bin\0KUBERNETES_SERVICE_HOST=10.0.0.1\0", &cgroups.0),
+            ["kubernetes", "containerd"]
+        );
+
+        let cgroups = ProcessCGroups::from_read("0::/user.slice\n".as_bytes()).unwrap();
+        assert_eq!(runtime_hints(b"container=podman\0", &cgroups.0), ["podman"]);
+        assert!(runtime_hints(b"HOME=/root\0", &cgroups.0).is_empty());
+    }
+
+    #[test]
+    fn test_cgroup_dir() {
+        let mounts = MountInfos::from_read(
+            "\
+30 23 0:26 / /sys/fs/cgroup/unified rw,nosuid shared:4 - cgroup2 cgroup2 rw,nsdelegate
+31 23 0:27 / /sys/fs/cgroup/systemd rw,nosuid shared:5 - cgroup cgroup rw,xattr,name=systemd
+32 23 0:28 / /sys/fs/cgroup/cpu,cpuacct rw,nosuid shared:6 - cgroup cgroup rw,cpu,cpuacct
+40 35 0:29 /docker/0123abcd /sys/fs/cgroup/memory ro,nosuid - cgroup cgroup rw,memory
+"
+            .as_bytes(),
+        )
+        .unwrap()
+        .0;
+        let cgroups = ProcessCGroups::from_read(
+            "\
+12:memory:/docker/0123abcd/app
+4:cpu,cpuacct:/user.slice
+1:name=systemd:/user.slice/session-1.scope
+0::/user.slice
+3:pids:/user.slice
+"
+            .as_bytes(),
+        )
+        .unwrap()
+        .0;
+        let root = Path::new("/proc/42/root");
+        let dirs: Vec<_> = cgroups
+            .iter()
+            .map(|cgroup| cgroup_dir(root, &mounts, cgroup))
+            .collect();
+        assert_eq!(
+            dirs,
+            [
+                Some("/proc/42/root/sys/fs/cgroup/memory/app".into()),
+                Some("/proc/42/root/sys/fs/cgroup/cpu,cpuacct/user.slice".into()),
+                Some("/proc/42/root/sys/fs/cgroup/systemd/user.slice/session-1.scope".into()),
+                Some("/proc/42/root/sys/fs/cgroup/unified/user.slice".into()),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_limits_v2() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("memory.max"), "536870912\n").unwrap();
+        fs::write(dir.path().join("memory.current"), "1048576\n").unwrap();
+        fs::write(dir.path().join("cpu.max"), "max 100000\n").unwrap();
+        fs::write(
+            dir.path().join("cpu.stat"),
+            "usage_usec 1234\nuser_usec 1000\nsystem_usec 234\n",
+        )
+        .unwrap();
+
+        let cgroups = ProcessCGroups::from_read("0::/test\n".as_bytes()).unwrap();
+        let mut info = MDRawContainerInfo::default();
+        read_limits(&cgroups.0[0], dir.path(), &mut info);
+        assert_eq!(info.memory_limit, 536870912);
+        assert_eq!(info.memory_usage, 1048576);
+        assert_eq!(info.cpu_quota_us, u64::MAX);
+        assert_eq!(info.cpu_period_us, 100000);
+        assert_eq!(info.cpu_usage_us, 1234);
+    }
+}
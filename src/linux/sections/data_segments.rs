@@ -0,0 +1,130 @@
+use super::*;
+use crate::linux::{
+    data_segments::DataSegmentsPolicy, ext_streams::SanitizedRegionKind, maps_reader::MappingInfo,
+    module_reader::DataSegments, register_memory,
+};
+use error_graph::WriteErrorList;
+use procfs_core::{
+    process::{MMPermissions, MMapPath, MemoryMaps},
+    FromRead,
+};
+use std::ops::Range;
+
+/// Copy the writable segments of the modules the policy selects into memory
+/// blocks, except for the memory that is already in the minidump.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
+    policy: &DataSegmentsPolicy,
+    mut soft_errors: impl WriteErrorList<errors::SectionDataSegmentsError>,
+) -> Result<(), errors::SectionDataSegmentsError> {
+    let entry_point = dumper.auxv.get_entry_address().map(|entry| entry as usize);
+    let mut budget = policy.max_size;
+    let mut covered: Vec<_> = config
+        .memory_blocks
+        .iter()
+        .map(|block| {
+            let start = block.start_of_memory_range as usize;
+            start..start + block.memory.data_size as usize
+        })
+        .collect();
+
+    for base_of_image in config
+        .module_infos
+        .iter()
+        .map(|info| info.base_of_image as usize)
+        .collect::<Vec<_>>()
+    {
+        let Some(mapping) = dumper.find_mapping(base_of_image) else {
+            continue;
+        };
+        let is_main_executable = entry_point.is_some_and(|entry| mapping.contains_address(entry));
+        let selected = (policy.main_executable && is_main_executable)
+            || policy
+                .module_patterns
+                .iter()
+                .any(|pattern| mapping.name_matches(pattern));
+        if !selected {
+            continue;
+        }
+
+        let segments =
+            match PtraceDumper::from_process_memory_for_address(base_of_image, dumper.pid) {
+                Ok(DataSegments(segments)) => segments
+                    .into_iter()
+                    .map(|segment| {
+                        base_of_image + segment.start as usize..base_of_image + segment.end as usize
+                    })
+                    .collect(),
+                Err(e) => {
+                    soft_errors.push(errors::SectionDataSegmentsError::ReadSegmentsFailed(
+                        base_of_image,
+                        e,
+                    ));
+                    writable_mappings_of(dumper, mapping)
+                }
+            };
+
+        for segment in register_memory::merge_and_subtract(segments, &covered) {
+            if budget == 0 {
+                return Ok(());
+            }
+            let length = segment.len().min(budget);
+            let mut data = match PtraceDumper::copy_from_process(dumper.pid, segment.start, length)
+            {
+                Ok(data) => data,
+                Err(e) => {
+                    soft_errors.push(errors::SectionDataSegmentsError::CopyFromProcessFailed(
+                        base_of_image,
+                        e,
+                    ));
+                    continue;
+                }
+            };
+            budget -= length;
+
+            if config.sanitize_stack {
+                config.sanitize_memory(
+                    dumper,
+                    SanitizedRegionKind::DataSegment,
+                    &mut data,
+                    segment.start,
+                    segment.start,
+                    0,
+                )?;
+            }
+            let section = MemoryArrayWriter::write_bytes(buffer, &data);
+            config.memory_blocks.push(MDMemoryDescriptor {
+                start_of_memory_range: segment.start as u64,
+                memory: section.location(),
+            });
+            covered.push(segment.start..segment.start + length);
+        }
+    }
+    Ok(())
+}
+
+/// The writable mappings of the file of `mapping`, and the anonymous writable
+/// mapping following them, which holds the `.bss` past the end of the file.
+/// These are merged into a single mapping in [`PtraceDumper::mappings`], so
+/// they are read from `/proc/$pid/maps` again.
+fn writable_mappings_of(dumper: &PtraceDumper, mapping: &MappingInfo) -> Vec<Range<usize>> {
+    let Some(maps) = std::fs::File::open(format!("/proc/{}/maps", dumper.pid))
+        .ok()
+        .and_then(|file| MemoryMaps::from_read(file).ok())
+    else {
+        return Vec::new();
+    };
+
+    let module = mapping.system_mapping_info.start_address..mapping.system_mapping_info.end_address;
+    maps.into_iter()
+        .filter(|map| map.perms.contains(MMPermissions::WRITE))
+        .map(|map| (map.address.0 as usize..map.address.1 as usize, map.pathname))
+        .filter(|(range, pathname)| {
+            module.contains(&range.start)
+                || (range.start == module.end && *pathname == MMapPath::Anonymous)
+        })
+        .map(|(range, _)| range)
+        .collect()
+}
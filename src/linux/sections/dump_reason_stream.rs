@@ -0,0 +1,39 @@
+use super::*;
+use crate::linux::{
+    ext_streams::{ExtStreamType, MDRawDumpReason},
+    minidump_writer::DumpReason,
+};
+
+/// Write the LinuxDumpReason stream for the reason set by the caller.
+pub fn write(
+    dump_reason: &DumpReason,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionDumpReasonError> {
+    let reason_rva = if dump_reason.reason.is_empty() {
+        0
+    } else {
+        write_string_to_location(buffer, &dump_reason.reason)?.rva
+    };
+
+    let header = MemoryWriter::alloc_with_val(
+        buffer,
+        MDRawDumpReason {
+            size_of_header: std::mem::size_of::<MDRawDumpReason>() as u32,
+            exception_code: dump_reason.exception_code,
+            reason_rva,
+            number_of_parameters: dump_reason.parameters.len() as u64,
+            ..Default::default()
+        },
+    )?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: ExtStreamType::LinuxDumpReason.into(),
+        location: header.location(),
+    };
+
+    let parameters = MemoryArrayWriter::<u64>::alloc_from_array(buffer, &dump_reason.parameters)?;
+
+    dirent.location.data_size += parameters.location().data_size;
+
+    Ok(dirent)
+}
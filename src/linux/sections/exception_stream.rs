@@ -1,16 +1,46 @@
 use super::minidump_writer::CrashingThreadContext;
 use super::*;
+use crate::linux::ext_streams::SiginfoIndex;
 use minidump_common::errors::ExceptionCodeLinux;
 
+/// The `siginfo` fields besides the signal, code and address, laid out as
+/// documented by [`SiginfoIndex`].
+fn siginfo_parameters(siginfo: &libc::signalfd_siginfo) -> [u64; 15] {
+    let mut parameters = [0u64; 15];
+    for (index, value) in [
+        (SiginfoIndex::Errno, siginfo.ssi_errno as i64 as u64),
+        (SiginfoIndex::Pid, siginfo.ssi_pid.into()),
+        (SiginfoIndex::Uid, siginfo.ssi_uid.into()),
+        (SiginfoIndex::Fd, siginfo.ssi_fd as i64 as u64),
+        (SiginfoIndex::Tid, siginfo.ssi_tid.into()),
+        (SiginfoIndex::Band, siginfo.ssi_band.into()),
+        (SiginfoIndex::Overrun, siginfo.ssi_overrun.into()),
+        (SiginfoIndex::Trapno, siginfo.ssi_trapno.into()),
+        (SiginfoIndex::Status, siginfo.ssi_status as i64 as u64),
+        (SiginfoIndex::Int, siginfo.ssi_int as i64 as u64),
+        (SiginfoIndex::Ptr, siginfo.ssi_ptr),
+        (SiginfoIndex::AddrLsb, siginfo.ssi_addr_lsb.into()),
+        (SiginfoIndex::Syscall, siginfo.ssi_syscall as i64 as u64),
+        (SiginfoIndex::CallAddr, siginfo.ssi_call_addr),
+        (SiginfoIndex::Arch, siginfo.ssi_arch.into()),
+    ] {
+        parameters[index as usize] = value;
+    }
+    parameters
+}
+
 pub fn write(
     config: &mut MinidumpWriter,
     buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
 ) -> Result<MDRawDirectory, errors::SectionExceptionStreamError> {
     let exception = if let Some(context) = &config.crash_context {
         MDException {
             exception_code: context.inner.siginfo.ssi_signo,
             exception_flags: context.inner.siginfo.ssi_code as u32,
             exception_address: context.inner.siginfo.ssi_addr,
+            number_parameters: 15,
+            exception_information: siginfo_parameters(&context.inner.siginfo),
             ..Default::default()
         }
     } else {
@@ -18,10 +48,25 @@ pub fn write(
             CrashingThreadContext::CrashContextPlusAddress((_, addr)) => *addr,
             _ => 0,
         };
-        MDException {
-            exception_code: ExceptionCodeLinux::DUMP_REQUESTED as u32,
-            exception_address: addr as u64,
-            ..Default::default()
+        match &config.dump_reason {
+            Some(dump_reason) => {
+                let mut exception_information = [0u64; 15];
+                let number_parameters = dump_reason.parameters.len().min(15);
+                exception_information[..number_parameters]
+                    .copy_from_slice(&dump_reason.parameters[..number_parameters]);
+                MDException {
+                    exception_code: dump_reason.exception_code,
+                    exception_address: addr as u64,
+                    number_parameters: number_parameters as u32,
+                    exception_information,
+                    ..Default::default()
+                }
+            }
+            None => MDException {
+                exception_code: ExceptionCodeLinux::DUMP_REQUESTED as u32,
+                exception_address: addr as u64,
+                ..Default::default()
+            },
         }
     };
 
@@ -35,7 +80,7 @@ pub fn write(
     };
 
     let stream = MDRawExceptionStream {
-        thread_id: config.blamed_thread as u32,
+        thread_id: config.reported_thread_id(dumper, config.blamed_thread) as u32,
         exception_record: exception,
         __align: 0,
         thread_context,
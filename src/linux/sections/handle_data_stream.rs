@@ -1,15 +1,222 @@
 use std::{
+    collections::HashMap,
     ffi::{CString, OsString},
     fs::{self, DirEntry},
     mem::{self},
+    net::SocketAddr,
     os::unix::prelude::OsStrExt,
     path::{Path, PathBuf},
 };
 
+use minidump_common::format::{
+    MINIDUMP_HANDLE_OBJECT_INFORMATION as MDRawHandleObjectInformation,
+    MINIDUMP_HANDLE_OBJECT_INFORMATION_TYPE,
+};
+use procfs_core::{
+    net::{TcpNetEntries, UdpNetEntries, UnixNetEntries},
+    ExplicitSystemInfo, FromRead, FromReadSI,
+};
+use scroll::ctx::{SizeWith, TryIntoCtx};
+
+use crate::linux::ext_streams::{
+    HandleObjectInfoKind, MDRawFdInfo, MDRawHandleDescriptor2, MDRawSocketInfo,
+};
 use crate::mem_writer::MemoryWriter;
 
 use super::*;
 
+/// A socket found in one of the tables of `/proc/$pid/net`.
+struct Socket {
+    info: MDRawSocketInfo,
+    /// The path of a unix socket
+    path: Option<PathBuf>,
+}
+
+/// The sockets of the network namespace of the process, by inode.
+///
+/// This is best effort, tables that can't be read are skipped.
+fn read_sockets(pid: i32) -> HashMap<u64, Socket> {
+    // The net tables only use the endianness, to decode the addresses
+    let system_info = ExplicitSystemInfo {
+        boot_time_secs: 0,
+        ticks_per_second: 0,
+        page_size: 0,
+        is_little_endian: cfg!(target_endian = "little"),
+    };
+    let net_path = |table: &str| format!("/proc/{pid}/net/{table}");
+
+    let mut sockets = HashMap::new();
+    let mut insert = |info: MDRawSocketInfo, path: Option<PathBuf>| {
+        // Sockets that are not referenced by any file descriptor anymore,
+        // e.g. in TIME_WAIT, have no inode
+        if info.inode != 0 {
+            sockets.insert(info.inode, Socket { info, path });
+        }
+    };
+
+    for table in ["tcp", "tcp6"] {
+        let Ok(entries) = TcpNetEntries::from_file(net_path(table), &system_info) else {
+            continue;
+        };
+        for entry in entries.0 {
+            insert(
+                inet_socket_info(
+                    entry.local_address,
+                    entry.remote_address,
+                    libc::SOCK_STREAM,
+                    entry.state.to_u8(),
+                    entry.inode,
+                ),
+                None,
+            );
+        }
+    }
+
+    for table in ["udp", "udp6"] {
+        let Ok(entries) = UdpNetEntries::from_file(net_path(table), &system_info) else {
+            continue;
+        };
+        for entry in entries.0 {
+            insert(
+                inet_socket_info(
+                    entry.local_address,
+                    entry.remote_address,
+                    libc::SOCK_DGRAM,
+                    entry.state.to_u8(),
+                    entry.inode,
+                ),
+                None,
+            );
+        }
+    }
+
+    if let Ok(entries) = UnixNetEntries::from_file(net_path("unix")) {
+        for entry in entries.0 {
+            insert(
+                MDRawSocketInfo {
+                    kind: HandleObjectInfoKind::Socket as u32,
+                    family: libc::AF_UNIX as u16,
+                    socket_type: entry.socket_type,
+                    inode: entry.inode,
+                    state: entry.state.to_u8().into(),
+                    ..Default::default()
+                },
+                entry.path,
+            );
+        }
+    }
+
+    sockets
+}
+
+fn inet_socket_info(
+    local: SocketAddr,
+    remote: SocketAddr,
+    socket_type: i32,
+    state: u8,
+    inode: u64,
+) -> MDRawSocketInfo {
+    fn address(addr: &SocketAddr) -> [u8; 16] {
+        let mut bytes = [0u8; 16];
+        match addr {
+            SocketAddr::V4(addr) => bytes[..4].copy_from_slice(&addr.ip().octets()),
+            SocketAddr::V6(addr) => bytes = addr.ip().octets(),
+        }
+        bytes
+    }
+
+    MDRawSocketInfo {
+        kind: HandleObjectInfoKind::Socket as u32,
+        family: if local.is_ipv4() {
+            libc::AF_INET
+        } else {
+            libc::AF_INET6
+        } as u16,
+        socket_type: socket_type as u16,
+        inode,
+        state: state.into(),
+        local_port: local.port(),
+        remote_port: remote.port(),
+        local_address: address(&local),
+        remote_address: address(&remote),
+        path_rva: 0,
+        __alignment: 0,
+    }
+}
+
+/// Parse the contents of `/proc/$pid/fdinfo/$fd`.
+fn parse_fdinfo(fdinfo: &str) -> MDRawFdInfo {
+    let mut info = MDRawFdInfo {
+        kind: HandleObjectInfoKind::FdInfo as u32,
+        ..Default::default()
+    };
+
+    for line in fdinfo.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "pos" => info.pos = value.parse().unwrap_or_default(),
+            "flags" => info.flags = u32::from_str_radix(value, 8).unwrap_or_default(),
+            "mnt_id" => info.mnt_id = value.parse().unwrap_or_default(),
+            _ => {}
+        }
+    }
+
+    info
+}
+
+/// The type name of a file descriptor, one of
+/// [`HANDLE_TYPE_NAMES`](crate::linux::ext_streams::HANDLE_TYPE_NAMES).
+fn handle_type_name(target: &Path, st_mode: u32) -> &'static str {
+    let target = target.as_os_str().as_bytes();
+    if target.starts_with(b"socket:") {
+        "socket"
+    } else if target.starts_with(b"pipe:") {
+        "pipe"
+    } else if let Some(kind) = target.strip_prefix(b"anon_inode:") {
+        match kind {
+            b"[eventfd]" => "eventfd",
+            b"[timerfd]" => "timerfd",
+            b"[signalfd]" => "signalfd",
+            b"inotify" => "inotify",
+            _ => "anon_inode",
+        }
+    } else if target.starts_with(b"/memfd:") {
+        "memfd"
+    } else {
+        match st_mode & libc::S_IFMT {
+            libc::S_IFDIR => "dir",
+            libc::S_IFIFO => "pipe",
+            libc::S_IFSOCK => "socket",
+            libc::S_IFCHR | libc::S_IFBLK => "device",
+            _ => "file",
+        }
+    }
+}
+
+/// Write a `MINIDUMP_HANDLE_OBJECT_INFORMATION` followed by `payload`,
+/// returning its RVA.
+fn write_object_info<T>(buffer: &mut DumpBuf, payload: T, next_info_rva: u32) -> Option<u32>
+where
+    T: TryIntoCtx<scroll::Endian, Error = scroll::Error> + SizeWith<scroll::Endian>,
+{
+    let header = MemoryWriter::alloc_with_val(
+        buffer,
+        MDRawHandleObjectInformation {
+            next_info_rva,
+            info_type: MINIDUMP_HANDLE_OBJECT_INFORMATION_TYPE::MiniHandleObjectInformationNone
+                as u32,
+            size_of_info: (mem::size_of::<MDRawHandleObjectInformation>()
+                + T::size_with(&scroll::Endian::Little)) as u32,
+        },
+    )
+    .ok()?;
+    MemoryWriter::alloc_with_val(buffer, payload).ok()?;
+    Some(header.location().rva)
+}
+
 fn file_stat(path: &Path) -> Option<libc::stat> {
     let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
     let mut stat = unsafe { std::mem::zeroed::<libc::stat>() };
@@ -22,25 +229,83 @@ fn file_stat(path: &Path) -> Option<libc::stat> {
     }
 }
 
-fn direntry_to_descriptor(buffer: &mut DumpBuf, entry: &DirEntry) -> Option<MDRawHandleDescriptor> {
+/// State shared by the descriptors of the stream.
+struct HandleContext {
+    pid: i32,
+    sockets: HashMap<u64, Socket>,
+    /// RVAs of the type names that were already written
+    type_names: HashMap<&'static str, u32>,
+}
+
+impl HandleContext {
+    fn type_name_rva(&mut self, buffer: &mut DumpBuf, name: &'static str) -> u32 {
+        if let Some(rva) = self.type_names.get(name) {
+            return *rva;
+        }
+        let rva = write_string_to_location(buffer, name)
+            .map(|location| location.rva)
+            .unwrap_or_default();
+        self.type_names.insert(name, rva);
+        rva
+    }
+
+    /// Write the object information of a descriptor: the fdinfo, followed by
+    /// the socket endpoints if it is a socket.
+    fn write_object_infos(&self, buffer: &mut DumpBuf, fd: u64, target: &Path) -> u32 {
+        let socket_rva = socket_inode(target)
+            .and_then(|inode| self.sockets.get(&inode))
+            .and_then(|socket| {
+                let mut info = socket.info;
+                if let Some(path) = &socket.path {
+                    info.path_rva = write_string_to_location(buffer, &path.to_string_lossy())
+                        .map(|location| location.rva)
+                        .unwrap_or_default();
+                }
+                write_object_info(buffer, info, 0)
+            })
+            .unwrap_or_default();
+
+        fs::read_to_string(format!("/proc/{}/fdinfo/{fd}", self.pid))
+            .ok()
+            .and_then(|fdinfo| write_object_info(buffer, parse_fdinfo(&fdinfo), socket_rva))
+            .unwrap_or(socket_rva)
+    }
+}
+
+/// The inode of a `socket:[inode]` link target.
+fn socket_inode(target: &Path) -> Option<u64> {
+    let target = target.to_str()?;
+    target
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+fn direntry_to_descriptor(
+    buffer: &mut DumpBuf,
+    context: &mut HandleContext,
+    entry: &DirEntry,
+) -> Option<MDRawHandleDescriptor2> {
     let handle = filename_to_fd(&entry.file_name())?;
     let realpath = fs::read_link(entry.path()).ok()?;
     let path_rva = write_string_to_location(buffer, realpath.to_string_lossy().as_ref()).ok()?;
     let stat = file_stat(&entry.path())?;
 
-    // TODO: We store the contents of `st_mode` into the `attributes` field, but
-    // we could also store a human-readable string of the file type inside
-    // `type_name_rva`. We might move this missing information (and
-    // more) inside a custom `MINIDUMP_HANDLE_OBJECT_INFORMATION_TYPE` blob.
-    // That would make this conversion loss-less.
-    Some(MDRawHandleDescriptor {
+    let type_name = handle_type_name(&realpath, stat.st_mode);
+    let type_name_rva = context.type_name_rva(buffer, type_name);
+    let object_info_rva = context.write_object_infos(buffer, handle, &realpath);
+
+    Some(MDRawHandleDescriptor2 {
         handle,
-        type_name_rva: 0,
+        type_name_rva,
         object_name_rva: path_rva.rva,
         attributes: stat.st_mode,
         granted_access: 0,
         handle_count: 0,
         pointer_count: 0,
+        object_info_rva,
+        reserved0: 0,
     })
 }
 
@@ -55,9 +320,14 @@ pub fn write(
 ) -> Result<MDRawDirectory, errors::SectionHandleDataStreamError> {
     let proc_fd_path = PathBuf::from(format!("/proc/{}/fd", config.process_id));
     let proc_fd_iter = fs::read_dir(proc_fd_path)?;
+    let mut context = HandleContext {
+        pid: config.process_id,
+        sockets: read_sockets(config.process_id),
+        type_names: HashMap::new(),
+    };
     let descriptors: Vec<_> = proc_fd_iter
         .filter_map(|entry| entry.ok())
-        .filter_map(|entry| direntry_to_descriptor(buffer, &entry))
+        .filter_map(|entry| direntry_to_descriptor(buffer, &mut context, &entry))
         .collect();
     let number_of_descriptors = descriptors.len() as u32;
 
@@ -65,7 +335,7 @@ pub fn write(
         buffer,
         MDRawHandleDataStream {
             size_of_header: mem::size_of::<MDRawHandleDataStream>() as u32,
-            size_of_descriptor: mem::size_of::<MDRawHandleDescriptor>() as u32,
+            size_of_descriptor: mem::size_of::<MDRawHandleDescriptor2>() as u32,
             number_of_descriptors,
             reserved: 0,
         },
@@ -77,8 +347,43 @@ pub fn write(
     };
 
     let descriptor_list =
-        MemoryArrayWriter::<MDRawHandleDescriptor>::alloc_from_iter(buffer, descriptors)?;
+        MemoryArrayWriter::<MDRawHandleDescriptor2>::alloc_from_iter(buffer, descriptors)?;
 
     dirent.location.data_size += descriptor_list.location().data_size;
     Ok(dirent)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fdinfo() {
+        let info = parse_fdinfo("pos:\t4096\nflags:\t0102002\nmnt_id:\t28\nino:\t1234\n");
+        assert_eq!(
+            info,
+            MDRawFdInfo {
+                kind: HandleObjectInfoKind::FdInfo as u32,
+                flags: 0o102002,
+                pos: 4096,
+                mnt_id: 28,
+                __alignment: 0,
+            }
+        );
+    }
+
+    #[test]
+    fn test_handle_type_name() {
+        let name = |target: &str, mode| handle_type_name(Path::new(target), mode);
+        assert_eq!(name("socket:[1234]", libc::S_IFSOCK), "socket");
+        assert_eq!(name("pipe:[1234]", libc::S_IFIFO), "pipe");
+        assert_eq!(name("anon_inode:[eventfd]", 0), "eventfd");
+        assert_eq!(name("anon_inode:inotify", 0), "inotify");
+        assert_eq!(name("anon_inode:[pidfd]", 0), "anon_inode");
+        assert_eq!(name("/memfd:jit (deleted)", libc::S_IFREG), "memfd");
+        assert_eq!(name("/dev/null", libc::S_IFCHR), "device");
+        assert_eq!(name("/tmp", libc::S_IFDIR), "dir");
+        assert_eq!(name("/etc/passwd", libc::S_IFREG), "file");
+        assert_eq!(socket_inode(Path::new("socket:[1234]")), Some(1234));
+    }
+}
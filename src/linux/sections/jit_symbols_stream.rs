@@ -0,0 +1,39 @@
+use super::*;
+use crate::linux::ext_streams::{ExtStreamType, MDRawJitSymbol, MDRawJitSymbolList};
+
+/// Write the LinuxJitSymbols stream for the JIT code modules written to the
+/// module list.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionJitSymbolsError> {
+    let mut symbols = Vec::with_capacity(config.jit_symbols.len());
+    for symbol in &config.jit_symbols {
+        symbols.push(MDRawJitSymbol {
+            address: symbol.address as u64,
+            size: symbol.size as u64,
+            name_rva: write_string_to_location(buffer, &symbol.name)?.rva,
+            ..Default::default()
+        });
+    }
+
+    let list_header = MemoryWriter::alloc_with_val(
+        buffer,
+        MDRawJitSymbolList {
+            size_of_header: std::mem::size_of::<MDRawJitSymbolList>() as u32,
+            size_of_entry: std::mem::size_of::<MDRawJitSymbol>() as u32,
+            number_of_entries: symbols.len() as u64,
+        },
+    )?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: ExtStreamType::LinuxJitSymbols.into(),
+        location: list_header.location(),
+    };
+
+    let entries = MemoryArrayWriter::<MDRawJitSymbol>::alloc_from_array(buffer, &symbols)?;
+
+    dirent.location.data_size += entries.location().data_size;
+
+    Ok(dirent)
+}
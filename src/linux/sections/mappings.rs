@@ -1,6 +1,18 @@
 use super::*;
-use crate::linux::maps_reader::MappingInfo;
-use crate::linux::module_reader::{BuildId, ReadFromModule, SoName};
+use crate::linux::dso_debug::{self, LinkMapEntry};
+use crate::linux::ext_streams::{
+    MDRawModuleInfo, MODULE_FLAG_ANONYMOUS, MODULE_FLAG_FILE_DELETED, MODULE_FLAG_FILE_HASH,
+    MODULE_FLAG_JIT, MODULE_FLAG_LINK_MAP, MODULE_FLAG_MEMFD, MODULE_FLAG_NO_BUILD_ID,
+    MODULE_FLAG_PRELOADED,
+};
+use crate::linux::jit_code::{self, JitCodeRegion};
+use crate::linux::maps_reader::{MappingInfo, SystemMappingInfo};
+use crate::linux::minidump_writer::ModuleSource;
+use crate::linux::module_reader::{BuildId, DebugLinks, ReadFromModule, SoName};
+use crate::linux::Pid;
+use error_graph::WriteErrorList;
+use procfs_core::process::MMPermissions;
+use std::{io::BufRead, os::unix::fs::MetadataExt, path::Path};
 
 /// Write information about the mappings in effect. Because we are using the
 /// minidump format, the information about the mappings is pretty limited.
@@ -10,11 +22,43 @@ pub fn write(
     config: &mut MinidumpWriter,
     buffer: &mut DumpBuf,
     dumper: &mut PtraceDumper,
+    mut soft_errors: impl WriteErrorList<errors::SectionMappingsError>,
 ) -> Result<MDRawDirectory, errors::SectionMappingsError> {
     let mut modules = Vec::new();
 
+    let link_map = match config.module_source {
+        ModuleSource::Maps => None,
+        ModuleSource::LinkMap => match dso_debug::read_dso_debug(dumper.pid, &dumper.auxv) {
+            Ok(dso_debug) => Some(dso_debug.link_map),
+            Err(e) => {
+                soft_errors.push(errors::SectionMappingsError::LinkMapUnavailable(e));
+                None
+            }
+        },
+    };
+    // Match the link_map against all the mappings before any of them are left
+    // out of the module list, so that their objects aren't reported as unmapped
+    let link_map_entries: Vec<Option<usize>> = dumper
+        .mappings
+        .iter()
+        .map(|mapping| {
+            link_map
+                .as_ref()?
+                .iter()
+                .position(|entry| is_link_map_entry_of(entry, mapping))
+        })
+        .collect();
+    let mut in_link_map = vec![false; link_map.as_ref().map_or(0, Vec::len)];
+    for &entry_idx in link_map_entries.iter().flatten() {
+        in_link_map[entry_idx] = true;
+    }
+    let preloaded = link_map
+        .as_ref()
+        .map(|_| preloaded_objects(dumper.pid))
+        .unwrap_or_default();
+
     // First write all the mappings from the dumper
-    for map_idx in 0..dumper.mappings.len() {
+    for (map_idx, &link_map_entry) in link_map_entries.iter().enumerate() {
         // If the mapping is uninteresting, or if
         // there is caller-provided information about this mapping
         // in the user_mapping_list list, skip it
@@ -32,16 +76,20 @@ pub fn write(
                 // from the file. If there is no note segment with the build id in
                 // the program headers, we can't get to the note section if the section header
                 // table isn't loaded.
-                if let Some(path) = &dumper.mappings[map_idx].name {
-                    let path = std::path::Path::new(&path);
-                    if path.exists() {
-                        log::debug!("failed to get build id from process memory ({e}), attempting to retrieve from {}", path.display());
-                        return BuildId::read_from_file(path)
-                            .map_err(errors::DumperError::ModuleReaderError);
-                    }
+                let mapping = &dumper.mappings[map_idx];
+                if let Some(path) = mapping
+                    .file_paths(dumper.pid)
+                    .into_iter()
+                    .find(|path| path.exists())
+                {
+                    log::debug!("failed to get build id from process memory ({e}), attempting to retrieve from {}", path.display());
+                    return BuildId::read_from_file(&path)
+                        .map_err(errors::DumperError::ModuleReaderError);
+                }
+                if let Some(name) = &mapping.name {
                     log::debug!(
                         "not attempting to get build id from {}: path does not exist",
-                        path.display()
+                        std::path::Path::new(name).display()
                     );
                 }
                 Err(e)
@@ -51,10 +99,16 @@ pub fn write(
                 BuildId(Vec::new())
             });
 
-        // If the identifier is all 0, its an uninteresting mapping (bmc#1676109)
-        if identifier.is_empty() || identifier.iter().all(|&x| x == 0) {
+        // If the identifier is all 0, its an uninteresting mapping (bmc#1676109),
+        // unless it is executable code from a file, which can still be
+        // identified by the file.
+        let has_build_id = !identifier.is_empty() && identifier.iter().any(|&x| x != 0);
+        let is_code_file =
+            dumper.mappings[map_idx].is_executable() && dumper.mappings[map_idx].name_is_path();
+        if !has_build_id && !is_code_file {
             continue;
         }
+        let identifier = if has_build_id { identifier } else { Vec::new() };
 
         // SONAME should always be accessible through program headers alone, so we don't really
         // need to fall back to trying to read from the mapping file.
@@ -63,14 +117,105 @@ pub fn write(
             .ok()
             .map(|SoName(n)| n);
 
-        let module = fill_raw_module(buffer, &dumper.mappings[map_idx], &identifier, soname)?;
+        let mapping = &dumper.mappings[map_idx];
+        let mut info = module_info(mapping, dumper.pid, config.hash_module_files);
+        if !has_build_id {
+            info.flags |= MODULE_FLAG_NO_BUILD_ID;
+        }
+        if let Some(link_map) = &link_map {
+            let Some(entry_idx) = link_map_entry else {
+                soft_errors.push(errors::SectionMappingsError::MappingNotInLinkMap {
+                    name: mapping
+                        .name
+                        .as_deref()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned(),
+                    start_address: mapping.start_address,
+                });
+                continue;
+            };
+
+            let entry = &link_map[entry_idx];
+            info.flags |= MODULE_FLAG_LINK_MAP;
+            info.load_bias = entry.addr as u64;
+            if is_preloaded(&entry.name, &preloaded) {
+                info.flags |= MODULE_FLAG_PRELOADED;
+            }
+        }
+
+        write_debug_links(buffer, dumper.pid, mapping, &mut info)?;
+        let module = fill_raw_module(buffer, dumper.pid, mapping, &identifier, soname)?;
+        config.module_infos.push(info);
+        modules.push(module);
+    }
+
+    // Objects the dynamic linker knows about, but which have no mapping, e.g.
+    // because they were unmapped behind its back
+    for (entry, _) in link_map
+        .iter()
+        .flatten()
+        .zip(&in_link_map)
+        .filter(|(_, found)| !**found)
+    {
+        soft_errors.push(errors::SectionMappingsError::LinkMapEntryNotMapped {
+            name: entry.name.clone(),
+            ld: entry.ld,
+        });
+    }
+
+    // Then the ELF images in anonymous mappings, which were loaded by something
+    // other than the dynamic linker (custom loaders, in-memory archives...)
+    for (mapping, identifier, soname) in anonymous_elf_modules(dumper) {
+        if mapping.is_contained_in(&config.user_mapping_list) {
+            continue;
+        }
+        let module = fill_raw_module(buffer, dumper.pid, &mapping, &identifier, soname)?;
+        let mut info = module_info(&mapping, dumper.pid, false);
+        info.flags |= MODULE_FLAG_ANONYMOUS;
+        config.module_infos.push(info);
         modules.push(module);
     }
 
     // Next write all the mappings provided by the caller
     for user in &config.user_mapping_list {
         // GUID was provided by caller.
-        let module = fill_raw_module(buffer, &user.mapping, &user.identifier, None)?;
+        let module = fill_raw_module(buffer, dumper.pid, &user.mapping, &user.identifier, None)?;
+        let mut info = module_info(&user.mapping, dumper.pid, config.hash_module_files);
+        write_debug_links(buffer, dumper.pid, &user.mapping, &mut info)?;
+        config.module_infos.push(info);
+        modules.push(module);
+    }
+
+    // And last the JIT code, which has no file nor build id. Code that is
+    // already covered by a module, or by a region from an earlier source, is
+    // left out: the regions set by the caller come first, then the ones read
+    // from the process.
+    let mut jit_code = config.jit_code.clone();
+    if config.ingest_jit_code {
+        jit_code.extend(read_jit_code(dumper, &mut soft_errors));
+    }
+    let mut covered: Vec<_> = modules
+        .iter()
+        .map(|module| module.base_of_image..module.base_of_image + module.size_of_image as u64)
+        .collect();
+    for region in jit_code {
+        let range = region.start_address as u64..(region.start_address + region.size) as u64;
+        if covered
+            .iter()
+            .any(|other| other.start < range.end && range.start < other.end)
+        {
+            log::debug!("skipping JIT code region {range:x?}, it is already covered");
+            continue;
+        }
+        covered.push(range);
+
+        let mapping = jit_code_mapping(&region);
+        let module = fill_raw_module(buffer, dumper.pid, &mapping, &[], None)?;
+        let mut info = module_info(&mapping, dumper.pid, false);
+        info.flags |= MODULE_FLAG_JIT;
+        config.module_infos.push(info);
+        config.jit_symbols.extend(region.symbols);
         modules.push(module);
     }
 
@@ -89,8 +234,264 @@ pub fn write(
     Ok(dirent)
 }
 
+/// Find ELF images in anonymous executable mappings. The ELF header is either
+/// at the start of the executable mapping, or at the start of a readable
+/// anonymous mapping directly preceding it.
+///
+/// Returns a synthesized mapping covering the image, named after its SONAME,
+/// or after its build id if it has none, along with the build id and SONAME.
+fn anonymous_elf_modules(dumper: &PtraceDumper) -> Vec<(MappingInfo, Vec<u8>, Option<String>)> {
+    let mut modules = Vec::new();
+    for (idx, mapping) in dumper.mappings.iter().enumerate() {
+        if mapping.name.is_some() || !mapping.is_executable() {
+            continue;
+        }
+        let preceding = idx
+            .checked_sub(1)
+            .map(|prev_idx| &dumper.mappings[prev_idx])
+            .filter(|prev| {
+                prev.name.is_none()
+                    && prev.is_readable()
+                    && !prev.is_executable()
+                    && prev.end_address() == mapping.start_address
+            });
+
+        for start in preceding.into_iter().chain(std::iter::once(mapping)) {
+            // This fails right away if there is no ELF header
+            let Ok(BuildId(identifier)) =
+                PtraceDumper::from_process_memory_for_mapping(start, dumper.pid)
+            else {
+                continue;
+            };
+            if identifier.is_empty() || identifier.iter().all(|&x| x == 0) {
+                continue;
+            }
+            let soname = PtraceDumper::from_process_memory_for_mapping(start, dumper.pid)
+                .ok()
+                .map(|SoName(n)| n);
+
+            log::debug!(
+                "found an ELF image in anonymous mapping at {:#x}",
+                start.start_address
+            );
+            let name = soname.clone().unwrap_or_else(|| {
+                let id: String = identifier.iter().map(|b| format!("{b:02x}")).collect();
+                format!("anonymous-elf-{id}")
+            });
+            let module = MappingInfo {
+                start_address: start.start_address,
+                size: mapping.end_address() - start.start_address,
+                system_mapping_info: SystemMappingInfo {
+                    start_address: start.system_mapping_info.start_address,
+                    end_address: mapping.system_mapping_info.end_address,
+                },
+                offset: 0,
+                permissions: start.permissions | mapping.permissions,
+                name: Some(name.into()),
+                deleted: false,
+                device: (0, 0),
+                inode: 0,
+                first_mapping_end: start.first_mapping_end,
+            };
+            modules.push((module, identifier, soname));
+            break;
+        }
+    }
+    modules
+}
+
+/// Read the JIT code from the GDB JIT interface and the perf map. The objects
+/// registered with the GDB JIT interface come first, as they are more precise
+/// than the mappings the perf map symbols are grouped by.
+fn read_jit_code(
+    dumper: &PtraceDumper,
+    soft_errors: &mut impl WriteErrorList<errors::SectionMappingsError>,
+) -> Vec<JitCodeRegion> {
+    let ns_pid = dumper
+        .threads
+        .iter()
+        .find(|thread| thread.tid == dumper.pid)
+        .and_then(|thread| thread.ns_tid)
+        .unwrap_or(dumper.pid);
+
+    let mut jit_code = Vec::new();
+    match jit_code::read_gdb_jit(
+        dumper.pid,
+        &dumper.mappings,
+        soft_errors.subwriter(errors::SectionMappingsError::GdbJitErrors),
+    ) {
+        Ok(regions) => jit_code.extend(regions),
+        Err(e) => soft_errors.push(errors::SectionMappingsError::ReadGdbJitFailed(e)),
+    }
+    match jit_code::read_perf_map(dumper.pid, ns_pid, &dumper.mappings) {
+        Ok(regions) => jit_code.extend(regions),
+        Err(e) => soft_errors.push(errors::SectionMappingsError::ReadPerfMapFailed(e)),
+    }
+    jit_code
+}
+
+/// A mapping covering a region of JIT code, to write it as a module.
+fn jit_code_mapping(region: &JitCodeRegion) -> MappingInfo {
+    MappingInfo {
+        start_address: region.start_address,
+        size: region.size,
+        system_mapping_info: SystemMappingInfo {
+            start_address: region.start_address,
+            end_address: region.start_address + region.size,
+        },
+        offset: 0,
+        permissions: MMPermissions::READ | MMPermissions::EXECUTE | MMPermissions::PRIVATE,
+        name: Some(region.name.clone().into()),
+        deleted: false,
+        device: (0, 0),
+        inode: 0,
+        first_mapping_end: 0,
+    }
+}
+
+/// Whether the `link_map` entry describes the object in `mapping`. The dynamic
+/// section of the object is part of its mapping, unless the loader mapped the
+/// segments with holes in between, so also compare the file names.
+fn is_link_map_entry_of(entry: &LinkMapEntry, mapping: &MappingInfo) -> bool {
+    (entry.ld != 0 && mapping.contains_address(entry.ld))
+        || (!entry.name.is_empty()
+            && mapping.name.as_deref() == Some(std::ffi::OsStr::new(&entry.name)))
+}
+
+/// The objects the dynamic linker is asked to preload, from `LD_PRELOAD` and
+/// `/etc/ld.so.preload` in the process' mount namespace.
+fn preloaded_objects(pid: Pid) -> Vec<String> {
+    let environ = std::fs::read(format!("/proc/{pid}/environ")).unwrap_or_default();
+    let ld_preload = environ
+        .split(|b| *b == 0)
+        .find_map(|var| var.strip_prefix(b"LD_PRELOAD="))
+        .map(String::from_utf8_lossy)
+        .unwrap_or_default();
+    let preload_file =
+        std::fs::read_to_string(format!("/proc/{pid}/root/etc/ld.so.preload")).unwrap_or_default();
+    parse_preload_list(&ld_preload, &preload_file)
+}
+
+/// `LD_PRELOAD` is separated by spaces or colons, `/etc/ld.so.preload` by
+/// whitespace.
+fn parse_preload_list(ld_preload: &str, preload_file: &str) -> Vec<String> {
+    ld_preload
+        .split([' ', ':'])
+        .chain(preload_file.split_whitespace())
+        .filter(|object| !object.is_empty())
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Preloaded objects are either paths, or file names searched in the library
+/// paths.
+fn is_preloaded(name: &str, preloaded: &[String]) -> bool {
+    let file_name = Path::new(name).file_name();
+    preloaded.iter().any(|object| {
+        object == name || (!object.contains('/') && file_name == Some(object.as_ref()))
+    })
+}
+
+/// The file of the module is only read when `hash_file` is set, as the threads
+/// of the process are stopped meanwhile.
+fn module_info(mapping: &MappingInfo, pid: Pid, hash_file: bool) -> MDRawModuleInfo {
+    let mut info = MDRawModuleInfo {
+        base_of_image: mapping.start_address as u64,
+        device_major: mapping.device.0,
+        device_minor: mapping.device.1,
+        inode: mapping.inode,
+        ..Default::default()
+    };
+    if mapping.deleted {
+        info.flags |= MODULE_FLAG_FILE_DELETED;
+    }
+    if mapping.is_memfd() {
+        info.flags |= MODULE_FLAG_MEMFD;
+    }
+
+    if let Some(metadata) = mapping
+        .file_paths(pid)
+        .into_iter()
+        .find_map(|path| std::fs::metadata(path).ok())
+    {
+        info.file_size = metadata.size();
+        info.file_mtime = metadata.mtime();
+    }
+    if hash_file {
+        if let Ok(file) = mapping.open_file(pid) {
+            match hash_file_contents(file) {
+                Ok(hash) => {
+                    info.file_hash = hash;
+                    info.flags |= MODULE_FLAG_FILE_HASH;
+                }
+                Err(e) => log::warn!("failed to hash {:?}: {e}", mapping.name),
+            }
+        }
+    }
+    info
+}
+
+/// Write the separate debug files of the module to the buffer, and reference
+/// them from `info`. The sections holding them usually aren't loaded, so they
+/// are read from the module file if possible.
+fn write_debug_links(
+    buffer: &mut DumpBuf,
+    pid: Pid,
+    mapping: &MappingInfo,
+    info: &mut MDRawModuleInfo,
+) -> Result<(), MemoryWriterError> {
+    let links = mapping
+        .file_paths(pid)
+        .into_iter()
+        .find(|path| path.exists())
+        .and_then(|path| DebugLinks::read_from_file(&path).ok())
+        .or_else(|| PtraceDumper::from_process_memory_for_mapping(mapping, pid).ok());
+    let Some(DebugLinks {
+        debuglink,
+        debugaltlink,
+    }) = links
+    else {
+        return Ok(());
+    };
+
+    if let Some((name, crc)) = debuglink {
+        info.debuglink_rva = write_string_to_location(buffer, &name)?.rva;
+        info.debuglink_crc = crc;
+    }
+    if let Some((name, build_id)) = debugaltlink {
+        info.debugaltlink_rva = write_string_to_location(buffer, &name)?.rva;
+        let build_id: String = build_id.iter().map(|b| format!("{b:02x}")).collect();
+        info.debugaltlink_build_id_rva = write_string_to_location(buffer, &build_id)?.rva;
+    }
+    Ok(())
+}
+
+/// Hash the contents of `file` with 64 bit FNV-1a, which is simple and stable,
+/// as the hash is meant to identify the file rather than to secure it.
+fn hash_file_contents(file: std::fs::File) -> std::io::Result<u64> {
+    const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+    const FNV_PRIME: u64 = 0x100000001b3;
+
+    let mut reader = std::io::BufReader::with_capacity(64 * 1024, file);
+    let mut hash = FNV_OFFSET_BASIS;
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok([]) => return Ok(hash),
+            Ok(chunk) => chunk,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        hash = chunk.iter().fold(hash, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        });
+        let read = chunk.len();
+        reader.consume(read);
+    }
+}
+
 fn fill_raw_module(
     buffer: &mut DumpBuf,
+    pid: Pid,
     mapping: &MappingInfo,
     identifier: &[u8],
     soname: Option<String>,
@@ -115,7 +516,7 @@ fn fill_raw_module(
     };
 
     let (file_path, _, so_version) = mapping
-        .get_mapping_effective_path_name_and_version(soname)
+        .get_mapping_effective_path_name_and_version(pid, soname)
         .map_err(|e| errors::SectionMappingsError::GetEffectivePathError(mapping.clone(), e))?;
     let name_header = write_string_to_location(buffer, file_path.to_string_lossy().as_ref())?;
 
@@ -140,3 +541,44 @@ fn fill_raw_module(
 
     Ok(raw_module)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_file_contents() {
+        let mut file = tempfile::tempfile().unwrap();
+        assert_eq!(
+            hash_file_contents(file.try_clone().unwrap()).unwrap(),
+            0xcbf29ce484222325
+        );
+        std::io::Write::write_all(&mut file, b"foobar").unwrap();
+        std::io::Seek::rewind(&mut file).unwrap();
+        assert_eq!(hash_file_contents(file).unwrap(), 0x85944171f73967e8);
+    }
+
+    #[test]
+    fn test_preloaded_objects() {
+        let preloaded = parse_preload_list(
+            "libfoo.so:/opt/lib/libbar.so libbaz.so",
+            "/usr/lib/libqux.so\n\n",
+        );
+        assert_eq!(
+            preloaded,
+            [
+                "libfoo.so",
+                "/opt/lib/libbar.so",
+                "libbaz.so",
+                "/usr/lib/libqux.so"
+            ]
+        );
+
+        assert!(is_preloaded("/usr/lib/libfoo.so", &preloaded));
+        assert!(is_preloaded("/opt/lib/libbar.so", &preloaded));
+        assert!(!is_preloaded("/usr/lib/libbar.so", &preloaded));
+        assert!(is_preloaded("/usr/lib/libqux.so", &preloaded));
+        assert!(!is_preloaded("/lib/libqux.so", &preloaded));
+        assert!(!is_preloaded("", &preloaded));
+    }
+}
@@ -1,6 +1,9 @@
 use super::*;
 use minidump_common::format::{MemoryProtection, MemoryState, MemoryType};
-use procfs_core::{process::MMPermissions, FromRead};
+use procfs_core::{
+    process::{MMPermissions, MMapPath, MemoryMap},
+    FromRead,
+};
 
 /// Write a MemoryInfoListStream using information from procfs.
 pub fn write(
@@ -11,13 +14,14 @@ pub fn write(
         "/proc/{}/maps",
         config.blamed_thread
     )))?;
+    let infos = memory_infos(&maps.0);
 
     let list_header = MemoryWriter::alloc_with_val(
         buffer,
         MDMemoryInfoList {
             size_of_header: std::mem::size_of::<MDMemoryInfoList>() as u32,
             size_of_entry: std::mem::size_of::<MDMemoryInfo>() as u32,
-            number_of_entries: maps.len() as u64,
+            number_of_entries: infos.len() as u64,
         },
     )?;
 
@@ -26,15 +30,58 @@ pub fn write(
         location: list_header.location(),
     };
 
-    let block_list = MemoryArrayWriter::<MDMemoryInfo>::alloc_from_iter(
-        buffer,
-        maps.iter().map(|mm| MDMemoryInfo {
+    let block_list = MemoryArrayWriter::<MDMemoryInfo>::alloc_from_iter(buffer, infos)?;
+
+    dirent.location.data_size += block_list.location().data_size;
+
+    Ok(dirent)
+}
+
+/// The allocation the previously visited mapping belongs to.
+struct Allocation<'a> {
+    base: u64,
+    end: u64,
+    protection: MemoryProtection,
+    first: &'a MemoryMap,
+    last: &'a MemoryMap,
+}
+
+fn memory_infos(maps: &[MemoryMap]) -> Vec<MDMemoryInfo> {
+    let mut infos = Vec::with_capacity(maps.len());
+    let mut allocation: Option<Allocation> = None;
+
+    for (idx, mm) in maps.iter().enumerate() {
+        let joins = allocation
+            .as_ref()
+            .is_some_and(|alloc| is_same_allocation(alloc, mm, maps.get(idx + 1)));
+
+        let alloc = match &mut allocation {
+            Some(alloc) if joins => {
+                alloc.end = mm.address.1;
+                alloc.last = mm;
+                alloc
+            }
+            _ => allocation.insert(Allocation {
+                base: mm.address.0,
+                end: mm.address.1,
+                protection: get_memory_protection(mm.perms),
+                first: mm,
+                last: mm,
+            }),
+        };
+
+        infos.push(MDMemoryInfo {
             base_address: mm.address.0,
-            allocation_base: mm.address.0,
-            allocation_protection: get_memory_protection(mm.perms).bits(),
+            allocation_base: alloc.base,
+            allocation_protection: alloc.protection.bits(),
             __alignment1: 0,
             region_size: mm.address.1 - mm.address.0,
-            state: MemoryState::MEM_COMMIT.bits(),
+            state: if is_reservation(mm) {
+                MemoryState::MEM_RESERVE
+            } else {
+                MemoryState::MEM_COMMIT
+            }
+            .bits(),
             protection: get_memory_protection(mm.perms).bits(),
             _type: if mm.perms.contains(MMPermissions::PRIVATE) {
                 MemoryType::MEM_PRIVATE
@@ -43,12 +90,53 @@ pub fn write(
             }
             .bits(),
             __alignment2: 0,
-        }),
-    )?;
+        });
+    }
 
-    dirent.location.data_size += block_list.location().data_size;
+    infos
+}
 
-    Ok(dirent)
+/// Whether `mm` is an anonymous private mapping without any access rights,
+/// i.e. address space that was reserved (a guard page, or the unused part of
+/// a large `PROT_NONE` reservation) but is not backed by memory.
+fn is_reservation(mm: &MemoryMap) -> bool {
+    mm.pathname == MMapPath::Anonymous && mm.perms == MMPermissions::PRIVATE
+}
+
+fn is_same_file(a: &MemoryMap, b: &MemoryMap) -> bool {
+    matches!(a.pathname, MMapPath::Path(_))
+        && a.pathname == b.pathname
+        && a.dev == b.dev
+        && a.inode == b.inode
+}
+
+/// Decide whether `mm` is another piece of the allocation `alloc` that
+/// directly precedes it.
+fn is_same_allocation(alloc: &Allocation, mm: &MemoryMap, next: Option<&MemoryMap>) -> bool {
+    if alloc.end != mm.address.0 {
+        return false;
+    }
+
+    match &mm.pathname {
+        // The segments of a file mapped by the dynamic linker.
+        MMapPath::Path(_) => is_same_file(alloc.first, mm),
+        MMapPath::Anonymous if is_reservation(mm) => {
+            // Address ranges the linker reserved for a library but which the
+            // library did not use. These appear as anonymous reservations
+            // right after the executable segment, or in between two segments
+            // of the same library.
+            matches!(alloc.first.pathname, MMapPath::Path(_))
+                && (alloc.last.perms.contains(MMPermissions::EXECUTE)
+                    || next.is_some_and(|next| {
+                        next.address.0 == mm.address.1 && is_same_file(alloc.first, next)
+                    }))
+        }
+        // Thread stacks are mapped with their guard area at the lowest
+        // addresses, so an accessible region directly above a lone
+        // reservation is considered to belong to it.
+        MMapPath::Anonymous => std::ptr::eq(alloc.first, alloc.last) && is_reservation(alloc.last),
+        _ => false,
+    }
 }
 
 fn get_memory_protection(permissions: MMPermissions) -> MemoryProtection {
@@ -66,3 +154,98 @@ fn get_memory_protection(permissions: MMPermissions) -> MemoryProtection {
         (true | false, true, true) => MemoryProtection::PAGE_EXECUTE_READWRITE,
     }
 }
+
+#[cfg(test)]
+#[cfg(target_pointer_width = "64")]
+mod tests {
+    use super::*;
+    use procfs_core::process::MemoryMaps;
+
+    fn get_infos_for(map: &str) -> Vec<MDMemoryInfo> {
+        let maps = MemoryMaps::from_read(map.as_bytes()).expect("failed to read mapping info");
+        memory_infos(&maps.0)
+    }
+
+    #[test]
+    fn test_library_allocation() {
+        let infos = get_infos_for(
+            "\
+7efd96bc4000-7efd96bea000 r--p 00000000 00:31 4996104                    /lib64/libc-2.32.so
+7efd96bea000-7efd96d39000 r-xp 00026000 00:31 4996104                    /lib64/libc-2.32.so
+7efd96d39000-7efd96d85000 r--p 00175000 00:31 4996104                    /lib64/libc-2.32.so
+7efd96d85000-7efd96d86000 ---p 00000000 00:00 0 
+7efd96d86000-7efd96d89000 r--p 001c1000 00:31 4996104                    /lib64/libc-2.32.so
+7efd96d89000-7efd96d8c000 rw-p 001c4000 00:31 4996104                    /lib64/libc-2.32.so
+7efd96d8c000-7efd96d92000 rw-p 00000000 00:00 0 ",
+        );
+
+        assert_eq!(infos.len(), 7);
+        for info in &infos[..6] {
+            assert_eq!(info.allocation_base, 0x7efd96bc4000);
+            assert_eq!(
+                info.allocation_protection,
+                MemoryProtection::PAGE_READONLY.bits()
+            );
+        }
+        assert_eq!(infos[3].state, MemoryState::MEM_RESERVE.bits());
+        assert_eq!(infos[3].protection, MemoryProtection::PAGE_NOACCESS.bits());
+        assert_eq!(infos[4].state, MemoryState::MEM_COMMIT.bits());
+        // Anonymous memory following a library is not part of it.
+        assert_eq!(infos[6].allocation_base, 0x7efd96d8c000);
+    }
+
+    #[test]
+    fn test_thread_stack_allocation() {
+        let infos = get_infos_for(
+            "\
+7f5a2c000000-7f5a2c021000 rw-p 00000000 00:00 0 
+7f5a2c021000-7f5a30000000 ---p 00000000 00:00 0 
+7f5a31400000-7f5a31401000 ---p 00000000 00:00 0 
+7f5a31401000-7f5a31c01000 rw-p 00000000 00:00 0 
+7f5a31c01000-7f5a31c02000 ---p 00000000 00:00 0 
+7f5a31c02000-7f5a32402000 rw-p 00000000 00:00 0 ",
+        );
+
+        let bases: Vec<_> = infos.iter().map(|info| info.allocation_base).collect();
+        assert_eq!(
+            bases,
+            [
+                0x7f5a2c000000,
+                0x7f5a2c021000,
+                0x7f5a31400000,
+                0x7f5a31400000,
+                0x7f5a31c01000,
+                0x7f5a31c01000,
+            ]
+        );
+
+        let states: Vec<_> = infos.iter().map(|info| info.state).collect();
+        let commit = MemoryState::MEM_COMMIT.bits();
+        let reserve = MemoryState::MEM_RESERVE.bits();
+        assert_eq!(states, [commit, reserve, reserve, commit, reserve, commit]);
+    }
+
+    #[test]
+    fn test_allocation_protection() {
+        let infos = get_infos_for(
+            "\
+7f5a31400000-7f5a31401000 ---p 00000000 00:00 0 
+7f5a31401000-7f5a31c01000 rw-p 00000000 00:00 0 
+7f5a32000000-7f5a32001000 r-xp 00000000 00:31 5004639                    /usr/lib64/libfoo.so
+7f5a32001000-7f5a32002000 rw-p 00001000 00:31 5004639                    /usr/lib64/libfoo.so",
+        );
+
+        // The protection of the first mapping of the allocation
+        let protections: Vec<_> = infos
+            .iter()
+            .map(|info| (info.allocation_protection, info.protection))
+            .collect();
+        let noaccess = MemoryProtection::PAGE_NOACCESS.bits();
+        let rw = MemoryProtection::PAGE_READWRITE.bits();
+        let rx = MemoryProtection::PAGE_EXECUTE_READ.bits();
+        assert_eq!(
+            protections,
+            [(noaccess, noaccess), (noaccess, rw), (rx, rx), (rx, rw)]
+        );
+    }
+}
@@ -0,0 +1,60 @@
+use super::*;
+use crate::linux::{ext_streams::SanitizedRegionKind, memory_rules, register_memory};
+use error_graph::WriteErrorList;
+use procfs_core::{process::MemoryMaps, FromRead};
+
+/// Copy the memory the memory rules include into memory blocks, except for the
+/// memory that is already in the minidump.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
+    mut soft_errors: impl WriteErrorList<errors::SectionMemoryRulesError>,
+) -> Result<(), errors::SectionMemoryRulesError> {
+    let maps = match MemoryMaps::from_file(format!("/proc/{}/maps", dumper.pid)) {
+        Ok(maps) => maps.into_iter().collect::<Vec<_>>(),
+        Err(e) => {
+            soft_errors.push(errors::SectionMemoryRulesError::ReadMapsFailed(e));
+            return Ok(());
+        }
+    };
+    let ranges = memory_rules::ranges_to_include(&config.memory_rules, &maps);
+    let covered: Vec<_> = config
+        .memory_blocks
+        .iter()
+        .map(|block| {
+            let start = block.start_of_memory_range as usize;
+            start..start + block.memory.data_size as usize
+        })
+        .collect();
+
+    for range in register_memory::merge_and_subtract(ranges, &covered) {
+        let mut data = match PtraceDumper::copy_from_process(dumper.pid, range.start, range.len()) {
+            Ok(data) => data,
+            Err(e) => {
+                soft_errors.push(errors::SectionMemoryRulesError::CopyFromProcessFailed(
+                    range.start,
+                    e,
+                ));
+                continue;
+            }
+        };
+        if config.sanitize_stack && config.sanitization_policy.sanitize_app_memory {
+            config.sanitize_memory(
+                dumper,
+                SanitizedRegionKind::AppMemory,
+                &mut data,
+                range.start,
+                range.start,
+                0,
+            )?;
+        }
+
+        let section = MemoryArrayWriter::write_bytes(buffer, &data);
+        config.memory_blocks.push(MDMemoryDescriptor {
+            start_of_memory_range: range.start as u64,
+            memory: section.location(),
+        });
+    }
+    Ok(())
+}
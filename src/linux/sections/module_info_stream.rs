@@ -0,0 +1,30 @@
+use super::*;
+use crate::linux::ext_streams::{ExtStreamType, MDRawModuleInfo, MDRawModuleInfoList};
+
+/// Write the LinuxModuleInfo stream for the modules collected while writing
+/// the module list.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionModuleInfoError> {
+    let list_header = MemoryWriter::alloc_with_val(
+        buffer,
+        MDRawModuleInfoList {
+            size_of_header: std::mem::size_of::<MDRawModuleInfoList>() as u32,
+            size_of_entry: std::mem::size_of::<MDRawModuleInfo>() as u32,
+            number_of_entries: config.module_infos.len() as u64,
+        },
+    )?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: ExtStreamType::LinuxModuleInfo.into(),
+        location: list_header.location(),
+    };
+
+    let entries =
+        MemoryArrayWriter::<MDRawModuleInfo>::alloc_from_array(buffer, &config.module_infos)?;
+
+    dirent.location.data_size += entries.location().data_size;
+
+    Ok(dirent)
+}
@@ -0,0 +1,82 @@
+use super::*;
+use crate::linux::ext_streams::{MDRawModuleInfo, MODULE_FLAG_JIT};
+use crate::linux::module_reader::ElfMetadata;
+use error_graph::WriteErrorList;
+use thread_list_stream::LIMIT_MINIDUMP_FUDGE_FACTOR;
+
+/// Copy the ELF metadata of the ELF modules in the module list into memory
+/// blocks, so that the module layout, build id notes, dynamic section and
+/// unwind table index are available without the module files. Regions that
+/// would exceed the minidump size limit are left out.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
+    mut soft_errors: impl WriteErrorList<errors::SectionModuleMetadataError>,
+) -> Result<(), errors::SectionModuleMetadataError> {
+    for base_of_image in config
+        .module_infos
+        .iter()
+        .filter(|info| is_elf_module(config, info, dumper))
+        .map(|info| info.base_of_image as usize)
+        .collect::<Vec<_>>()
+    {
+        let ElfMetadata(regions) =
+            match PtraceDumper::from_process_memory_for_address(base_of_image, dumper.pid) {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    soft_errors.push(errors::SectionModuleMetadataError::ReadMetadataFailed(
+                        base_of_image,
+                        e,
+                    ));
+                    continue;
+                }
+            };
+
+        for region in regions {
+            let start = base_of_image + region.start as usize;
+            let length = (region.end - region.start) as usize;
+            if let Some(minidump_size_limit) = config.minidump_size_limit {
+                let available = minidump_size_limit
+                    .saturating_sub(buffer.position() + LIMIT_MINIDUMP_FUDGE_FACTOR);
+                if length as u64 > available {
+                    log::debug!("skipping ELF metadata at {start:#x}, it exceeds the size limit");
+                    continue;
+                }
+            }
+            let data = match PtraceDumper::copy_from_process(dumper.pid, start, length) {
+                Ok(data) => data,
+                Err(e) => {
+                    soft_errors.push(errors::SectionModuleMetadataError::CopyFromProcessFailed(
+                        base_of_image,
+                        e,
+                    ));
+                    continue;
+                }
+            };
+
+            let section = MemoryArrayWriter::write_bytes(buffer, &data);
+            config.memory_blocks.push(MDMemoryDescriptor {
+                start_of_memory_range: start as u64,
+                memory: section.location(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Whether the module starts with an ELF header the metadata can be read from.
+/// JIT code and the modules of the user mapping list aren't mapped from an ELF
+/// image, so they are left out without looking at their memory.
+fn is_elf_module(config: &MinidumpWriter, info: &MDRawModuleInfo, dumper: &PtraceDumper) -> bool {
+    if info.flags & MODULE_FLAG_JIT != 0
+        || config
+            .user_mapping_list
+            .iter()
+            .any(|user| user.mapping.start_address as u64 == info.base_of_image)
+    {
+        return false;
+    }
+    PtraceDumper::copy_from_process(dumper.pid, info.base_of_image as usize, 4)
+        .is_ok_and(|magic| magic == goblin::elf::header::ELFMAG)
+}
@@ -0,0 +1,32 @@
+use super::*;
+use crate::linux::{
+    ext_streams::{ExtStreamType, MDRawRustPanic},
+    panic_hook::RustPanic,
+};
+
+/// Write the LinuxRustPanic stream for the panic the dump is written for.
+pub fn write(
+    rust_panic: &RustPanic,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionRustPanicError> {
+    let thread_name_rva = match &rust_panic.thread_name {
+        Some(name) => write_string_to_location(buffer, name)?.rva,
+        None => 0,
+    };
+    let raw = MDRawRustPanic {
+        thread_id: rust_panic.thread_id as u32,
+        line: rust_panic.line,
+        column: rust_panic.column,
+        message_rva: write_string_to_location(buffer, &rust_panic.message)?.rva,
+        file_rva: write_string_to_location(buffer, &rust_panic.file)?.rva,
+        thread_name_rva,
+        backtrace_rva: write_string_to_location(buffer, &rust_panic.backtrace)?.rva,
+        ..Default::default()
+    };
+
+    let stream = MemoryWriter::alloc_with_val(buffer, raw)?;
+    Ok(MDRawDirectory {
+        stream_type: ExtStreamType::LinuxRustPanic.into(),
+        location: stream.location(),
+    })
+}
@@ -0,0 +1,32 @@
+use super::*;
+use crate::linux::ext_streams::{ExtStreamType, MDRawSanitizationStatsList, MDRawSanitizedRegion};
+
+/// Write the LinuxSanitizationStats stream for the regions sanitized while
+/// writing the thread list and the application memory.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionSanitizationStatsError> {
+    let list_header = MemoryWriter::alloc_with_val(
+        buffer,
+        MDRawSanitizationStatsList {
+            size_of_header: std::mem::size_of::<MDRawSanitizationStatsList>() as u32,
+            size_of_entry: std::mem::size_of::<MDRawSanitizedRegion>() as u32,
+            number_of_entries: config.sanitized_regions.len() as u64,
+        },
+    )?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: ExtStreamType::LinuxSanitizationStats.into(),
+        location: list_header.location(),
+    };
+
+    let entries = MemoryArrayWriter::<MDRawSanitizedRegion>::alloc_from_array(
+        buffer,
+        &config.sanitized_regions,
+    )?;
+
+    dirent.location.data_size += entries.location().data_size;
+
+    Ok(dirent)
+}
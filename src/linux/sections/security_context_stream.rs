@@ -0,0 +1,82 @@
+use super::*;
+use crate::linux::ext_streams::{ExtStreamType, MDRawSecurityContext};
+use procfs_core::{process::Status, FromRead};
+use std::fs;
+
+/// Write a LinuxSecurityContext stream with the credentials and sandboxing
+/// state of the blamed thread.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionSecurityContextError> {
+    // Credentials and seccomp are per thread, report the ones of the thread
+    // that crashed.
+    let status_path = format!("/proc/{}/status", config.blamed_thread);
+    let status_text = fs::read_to_string(status_path)?;
+    let status = Status::from_read(status_text.as_bytes())?;
+
+    let mut context = security_context(&status, &status_text);
+    context.ptrace_scope = fs::read_to_string("/proc/sys/kernel/yama/ptrace_scope")
+        .ok()
+        .and_then(|scope| scope.trim().parse().ok())
+        .unwrap_or(u32::MAX);
+
+    // Without an LSM this is empty, or reading it fails
+    let label = fs::read(format!("/proc/{}/attr/current", config.blamed_thread))
+        .map(|label| {
+            String::from_utf8_lossy(&label)
+                .trim_end_matches(['\0', '\n'])
+                .to_owned()
+        })
+        .unwrap_or_default();
+    if !label.is_empty() {
+        context.lsm_label_rva = write_string_to_location(buffer, &label)?.rva;
+    }
+
+    let header = MemoryWriter::alloc_with_val(buffer, context)?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: ExtStreamType::LinuxSecurityContext.into(),
+        location: header.location(),
+    };
+
+    let groups =
+        MemoryArrayWriter::<u32>::alloc_from_iter(buffer, status.groups.iter().map(|g| *g as u32))?;
+
+    dirent.location.data_size += groups.location().data_size;
+
+    Ok(dirent)
+}
+
+fn security_context(status: &Status, status_text: &str) -> MDRawSecurityContext {
+    // procfs doesn't parse this one
+    let seccomp_filters = status_text
+        .lines()
+        .find_map(|line| line.strip_prefix("Seccomp_filters:"))
+        .and_then(|filters| filters.trim().parse().ok())
+        .unwrap_or_default();
+
+    MDRawSecurityContext {
+        size_of_header: std::mem::size_of::<MDRawSecurityContext>() as u32,
+        number_of_groups: status.groups.len() as u32,
+        real_uid: status.ruid,
+        effective_uid: status.euid,
+        saved_uid: status.suid,
+        filesystem_uid: status.fuid,
+        real_gid: status.rgid,
+        effective_gid: status.egid,
+        saved_gid: status.sgid,
+        filesystem_gid: status.fgid,
+        cap_inheritable: status.capinh,
+        cap_permitted: status.capprm,
+        cap_effective: status.capeff,
+        cap_bounding: status.capbnd.unwrap_or_default(),
+        cap_ambient: status.capamb.unwrap_or_default(),
+        no_new_privs: status.nonewprivs.unwrap_or_default() as u32,
+        seccomp_mode: status.seccomp.unwrap_or_default(),
+        seccomp_filters,
+        ptrace_scope: u32::MAX,
+        lsm_label_rva: 0,
+        __alignment: 0,
+    }
+}
@@ -0,0 +1,30 @@
+use super::*;
+use crate::linux::ext_streams::{ExtStreamType, MDRawSignalFrame, MDRawSignalFrameList};
+
+/// Write the LinuxSignalFrames stream for the signal frames found when
+/// writing the thread list.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionSignalFramesError> {
+    let list_header = MemoryWriter::alloc_with_val(
+        buffer,
+        MDRawSignalFrameList {
+            size_of_header: std::mem::size_of::<MDRawSignalFrameList>() as u32,
+            size_of_entry: std::mem::size_of::<MDRawSignalFrame>() as u32,
+            number_of_entries: config.signal_frames.len() as u64,
+        },
+    )?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: ExtStreamType::LinuxSignalFrames.into(),
+        location: list_header.location(),
+    };
+
+    let entries =
+        MemoryArrayWriter::<MDRawSignalFrame>::alloc_from_array(buffer, &config.signal_frames)?;
+
+    dirent.location.data_size += entries.location().data_size;
+
+    Ok(dirent)
+}
@@ -0,0 +1,64 @@
+use super::*;
+use crate::linux::ext_streams::{ExtStreamType, MDRawSmapsEntry, MDRawSmapsList};
+use crate::linux::minidump_writer::SmapsMode;
+use procfs_core::{
+    process::{MemoryMap, MemoryMaps, SmapsRollup},
+    FromRead,
+};
+
+/// Write a LinuxSmaps or LinuxSmapsRollup stream, depending on `mode`.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+    mode: SmapsMode,
+) -> Result<MDRawDirectory, errors::SectionSmapsError> {
+    let (stream_type, maps) = match mode {
+        SmapsMode::Mappings => (
+            ExtStreamType::LinuxSmaps,
+            MemoryMaps::from_file(format!("/proc/{}/smaps", config.blamed_thread))?,
+        ),
+        SmapsMode::Rollup => (
+            ExtStreamType::LinuxSmapsRollup,
+            SmapsRollup::from_file(format!("/proc/{}/smaps_rollup", config.blamed_thread))?
+                .memory_map_rollup,
+        ),
+    };
+
+    let list_header = MemoryWriter::alloc_with_val(
+        buffer,
+        MDRawSmapsList {
+            size_of_header: std::mem::size_of::<MDRawSmapsList>() as u32,
+            size_of_entry: std::mem::size_of::<MDRawSmapsEntry>() as u32,
+            number_of_entries: maps.len() as u64,
+        },
+    )?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: stream_type.into(),
+        location: list_header.location(),
+    };
+
+    let entries = MemoryArrayWriter::<MDRawSmapsEntry>::alloc_from_iter(
+        buffer,
+        maps.iter().map(smaps_entry),
+    )?;
+
+    dirent.location.data_size += entries.location().data_size;
+
+    Ok(dirent)
+}
+
+fn smaps_entry(mm: &MemoryMap) -> MDRawSmapsEntry {
+    let value = |key: &str| mm.extension.map.get(key).copied().unwrap_or_default();
+    MDRawSmapsEntry {
+        base_address: mm.address.0,
+        region_size: mm.address.1.saturating_sub(mm.address.0),
+        rss: value("Rss"),
+        pss: value("Pss"),
+        private_dirty: value("Private_Dirty"),
+        swap: value("Swap"),
+        anon_huge_pages: value("AnonHugePages"),
+        vm_flags: mm.extension.vm_flags.bits(),
+        __alignment: 0,
+    }
+}
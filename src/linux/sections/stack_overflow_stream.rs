@@ -0,0 +1,30 @@
+use super::*;
+use crate::linux::ext_streams::{ExtStreamType, MDRawStackOverflow, MDRawStackOverflowList};
+
+/// Write the LinuxStackOverflow stream for the overflows found when writing
+/// the thread list.
+pub fn write(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+) -> Result<MDRawDirectory, errors::SectionStackOverflowError> {
+    let list_header = MemoryWriter::alloc_with_val(
+        buffer,
+        MDRawStackOverflowList {
+            size_of_header: std::mem::size_of::<MDRawStackOverflowList>() as u32,
+            size_of_entry: std::mem::size_of::<MDRawStackOverflow>() as u32,
+            number_of_entries: config.stack_overflows.len() as u64,
+        },
+    )?;
+
+    let mut dirent = MDRawDirectory {
+        stream_type: ExtStreamType::LinuxStackOverflow.into(),
+        location: list_header.location(),
+    };
+
+    let entries =
+        MemoryArrayWriter::<MDRawStackOverflow>::alloc_from_array(buffer, &config.stack_overflows)?;
+
+    dirent.location.data_size += entries.location().data_size;
+
+    Ok(dirent)
+}
@@ -1,7 +1,19 @@
-use std::cmp::min;
+use std::{cmp::min, ops::Range};
 
 use super::*;
-use crate::{minidump_cpu::RawContextCPU, minidump_writer::CrashingThreadContext};
+use crate::{
+    linux::{
+        ext_streams::{
+            MDRawSignalFrame, MDRawSignalStack, MDRawStackOverflow, SanitizedRegionKind,
+            STACK_OVERFLOW_FLAG_FAULT_ADDRESS, STACK_OVERFLOW_FLAG_STACK_POINTER,
+        },
+        register_memory::{self, RegisterClass},
+        signal_frame::{self, SignalFrame},
+    },
+    minidump_cpu::RawContextCPU,
+    minidump_writer::CrashingThreadContext,
+    Pid,
+};
 
 // The following kLimit* constants are for when minidump_size_limit_ is set
 // and the minidump size might exceed it.
@@ -17,7 +29,13 @@ const LIMIT_BASE_THREAD_COUNT: usize = 20;
 const LIMIT_MAX_EXTRA_THREAD_STACK_LEN: usize = 2 * 1024;
 // Make sure this number of additional bytes can fit in the minidump
 // (exclude the stack data).
-const LIMIT_MINIDUMP_FUDGE_FACTOR: u64 = 64 * 1024;
+pub(super) const LIMIT_MINIDUMP_FUDGE_FACTOR: u64 = 64 * 1024;
+// How much of the deepest part of an overflowed stack to include in the
+// minidump, regardless of the limits above.
+const STACK_OVERFLOW_CAPTURE_LEN: usize = 64 * 1024;
+// Maximum size of the stack of the signal handler of the crashing thread to
+// include in the minidump.
+const MAX_SIGNAL_STACK_LEN: usize = 1024 * 1024;
 
 #[derive(Debug, Clone, Copy)]
 enum MaxStackLen {
@@ -59,6 +77,8 @@ pub fn write(
         }
     }
 
+    let mut register_windows = Vec::new();
+    let mut thread_pointer_windows = Vec::new();
     for (idx, item) in dumper.threads.clone().iter().enumerate() {
         let mut thread = MDRawThread {
             thread_id: item.tid.try_into()?,
@@ -75,9 +95,15 @@ pub fn write(
         // signal handler with the alternative stack, which would be deeply
         // unhelpful.
         if config.crash_context.is_some() && thread.thread_id == config.blamed_thread as u32 {
+            thread.teb = dumper
+                .get_thread_info_by_index(idx)
+                .map_or(0, |info| info.thread_pointer as u64);
             let crash_context = config.crash_context.as_ref().unwrap();
             let instruction_ptr = crash_context.get_instruction_pointer();
             let stack_pointer = crash_context.get_stack_pointer();
+            let siginfo = &crash_context.inner.siginfo;
+            let fault_address =
+                (siginfo.ssi_signo == libc::SIGSEGV as u32).then_some(siginfo.ssi_addr as usize);
             fill_thread_stack(
                 config,
                 buffer,
@@ -87,6 +113,18 @@ pub fn write(
                 stack_pointer,
                 MaxStackLen::None,
             )?;
+            detect_stack_overflow(
+                config,
+                buffer,
+                dumper,
+                &thread,
+                item.tid,
+                stack_pointer,
+                fault_address,
+            )?;
+            if config.capture_signal_stack {
+                write_signal_stack(config, buffer, dumper, &thread, idx, item.tid)?;
+            }
             // Copy 256 bytes around crashing instruction pointer to minidump.
             let ip_memory_size: usize = 256;
             // Bound it to the upper and lower bounds of the memory map
@@ -115,11 +153,21 @@ pub fn write(
                 ip_memory_d.memory.data_size =
                     (end_of_range - ip_memory_d.start_of_memory_range) as u32;
 
-                let memory_copy = PtraceDumper::copy_from_process(
+                let mut memory_copy = PtraceDumper::copy_from_process(
                     thread.thread_id as i32,
                     ip_memory_d.start_of_memory_range as _,
                     ip_memory_d.memory.data_size as usize,
                 )?;
+                if config.sanitize_stack && config.sanitization_policy.sanitize_ip_memory {
+                    config.sanitize_memory(
+                        dumper,
+                        SanitizedRegionKind::InstructionPointer,
+                        &mut memory_copy,
+                        ip_memory_d.start_of_memory_range as usize,
+                        instruction_ptr,
+                        0,
+                    )?;
+                }
 
                 let mem_section = MemoryArrayWriter::alloc_from_array(buffer, &memory_copy)?;
                 ip_memory_d.memory = mem_section.location();
@@ -131,6 +179,14 @@ pub fn write(
             let mut cpu: RawContextCPU = Default::default();
             let crash_context = config.crash_context.as_ref().unwrap();
             crash_context.fill_cpu_context(&mut cpu);
+            if config.sanitize_stack && config.sanitization_policy.sanitize_registers {
+                dumper.sanitize_cpu_context(&config.sanitization_policy, &mut cpu, stack_pointer);
+            }
+            let siginfo = &config.crash_context.as_ref().unwrap().inner.siginfo;
+            let signal_address = [libc::SIGSEGV, libc::SIGBUS]
+                .contains(&(siginfo.ssi_signo as i32))
+                .then_some(siginfo.ssi_addr as usize);
+            register_windows.extend(find_register_windows(config, dumper, &cpu, signal_address));
             let cpu_section = MemoryWriter::alloc_with_val(buffer, cpu)?;
             thread.thread_context = cpu_section.location();
 
@@ -138,25 +194,79 @@ pub fn write(
                 CrashingThreadContext::CrashContext(cpu_section.location());
         } else {
             let info = dumper.get_thread_info_by_index(idx)?;
+            thread.teb = info.thread_pointer as u64;
             let max_stack_len =
                 if config.minidump_size_limit.is_some() && idx >= LIMIT_BASE_THREAD_COUNT {
                     extra_thread_stack_len
                 } else {
                     MaxStackLen::None // default to no maximum for this thread
                 };
-            let instruction_ptr = info.get_instruction_pointer();
+            // A thread running a signal handler is recorded as the signal
+            // interrupted it, like the crashing thread
+            let signal_frame = if config.recover_signal_frames {
+                signal_frame::find_signal_frame(dumper, item.tid, info.stack_pointer)
+            } else {
+                None
+            };
+            let (instruction_ptr, stack_pointer) = match &signal_frame {
+                Some(frame) => (
+                    frame.context.get_instruction_pointer(),
+                    frame.context.get_stack_pointer(),
+                ),
+                None => (info.get_instruction_pointer(), info.stack_pointer),
+            };
             fill_thread_stack(
                 config,
                 buffer,
                 dumper,
                 &mut thread,
                 instruction_ptr,
-                info.stack_pointer,
+                stack_pointer,
                 max_stack_len,
             )?;
+            detect_stack_overflow(
+                config,
+                buffer,
+                dumper,
+                &thread,
+                item.tid,
+                stack_pointer,
+                None,
+            )?;
 
             let mut cpu = RawContextCPU::default();
-            info.fill_cpu_context(&mut cpu);
+            match &signal_frame {
+                Some(frame) => {
+                    write_handler_stack(
+                        config,
+                        buffer,
+                        dumper,
+                        &thread,
+                        item.tid,
+                        info.stack_pointer,
+                        frame,
+                    )?;
+                    config.signal_frames.push(MDRawSignalFrame {
+                        thread_id: config.reported_thread_id(dumper, item.tid).try_into()?,
+                        signal: frame.signal,
+                        frame_address: frame.address as u64,
+                        handler_stack_pointer: info.stack_pointer as u64,
+                        handler_instruction_pointer: info.get_instruction_pointer() as u64,
+                    });
+                    frame.context.fill_cpu_context(&mut cpu);
+                }
+                None => info.fill_cpu_context(&mut cpu),
+            }
+            if config.sanitize_stack && config.sanitization_policy.sanitize_registers {
+                dumper.sanitize_cpu_context(&config.sanitization_policy, &mut cpu, stack_pointer);
+            }
+            let all_threads = config
+                .register_memory_policy
+                .as_ref()
+                .is_some_and(|policy| policy.all_threads);
+            if all_threads || item.tid == config.blamed_thread {
+                register_windows.extend(find_register_windows(config, dumper, &cpu, None));
+            }
             let cpu_section = MemoryWriter::<RawContextCPU>::alloc_with_val(buffer, cpu)?;
             thread.thread_context = cpu_section.location();
             if item.tid == config.blamed_thread {
@@ -169,8 +279,34 @@ pub fn write(
                 ));
             }
         }
+        if config.thread_pointer_window > 0 {
+            let size = config.thread_pointer_window;
+            let thread_pointer = thread.teb as usize;
+            let start = thread_pointer.saturating_sub(size / 2);
+            thread_pointer_windows.extend(readable_window(
+                dumper,
+                thread_pointer,
+                start..start.saturating_add(size),
+            ));
+        }
+        // The dumper's id is needed to access the thread above
+        thread.thread_id = config.reported_thread_id(dumper, item.tid).try_into()?;
         thread_list.set_value_at(buffer, thread, idx)?;
     }
+    write_memory_windows(
+        config,
+        buffer,
+        dumper,
+        register_windows,
+        SanitizedRegionKind::RegisterMemory,
+    )?;
+    write_memory_windows(
+        config,
+        buffer,
+        dumper,
+        thread_pointer_windows,
+        SanitizedRegionKind::ThreadPointer,
+    )?;
     Ok(dirent)
 }
 
@@ -216,7 +352,14 @@ fn fill_thread_stack(
         }
 
         if config.sanitize_stack {
-            dumper.sanitize_stack_copy(&mut stack_bytes, stack_ptr, stack_pointer_offset)?;
+            config.sanitize_memory(
+                dumper,
+                SanitizedRegionKind::Stack,
+                &mut stack_bytes,
+                valid_stack_ptr,
+                stack_ptr,
+                stack_pointer_offset,
+            )?;
         }
 
         let stack_location = MDLocationDescriptor {
@@ -230,3 +373,293 @@ fn fill_thread_stack(
     }
     Ok(())
 }
+
+/// Record a stack overflow if the stack pointer of the thread, or the address
+/// of the fault that crashed it, is in the guard below its stack. The deepest
+/// part of the stack is included in the minidump then, as far as the stack of
+/// the thread doesn't already cover it.
+fn detect_stack_overflow(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
+    thread: &MDRawThread,
+    tid: Pid,
+    stack_ptr: usize,
+    fault_address: Option<usize>,
+) -> Result<(), errors::SectionThreadListError> {
+    let mut flags = 0;
+    let mut guard = dumper.find_stack_guard(stack_ptr);
+    if guard.is_some() {
+        flags |= STACK_OVERFLOW_FLAG_STACK_POINTER;
+    }
+    if let Some(fault_address) = fault_address {
+        // Only the guard of the stack of this thread, not any unmapped memory
+        // below some writable mapping
+        let stack_start = match &guard {
+            Some(guard) => Some(guard.end),
+            None => dumper
+                .find_mapping(stack_ptr)
+                .map(|mapping| mapping.start_address),
+        };
+        if let Some(fault_guard) = dumper
+            .find_stack_guard(fault_address)
+            .filter(|fault_guard| Some(fault_guard.end) == stack_start)
+        {
+            flags |= STACK_OVERFLOW_FLAG_FAULT_ADDRESS;
+            guard = Some(fault_guard);
+        }
+    }
+    let Some(guard) = guard else {
+        return Ok(());
+    };
+    log::warn!("stack overflow in thread {tid}: {stack_ptr:#x} is near the guard {guard:x?}");
+
+    config.stack_overflows.push(MDRawStackOverflow {
+        thread_id: config.reported_thread_id(dumper, tid).try_into()?,
+        flags,
+        stack_pointer: stack_ptr as u64,
+        fault_address: fault_address.unwrap_or_default() as u64,
+        guard_start: guard.start as u64,
+        guard_end: guard.end as u64,
+    });
+
+    let Some(stack_mapping) = dumper.find_mapping(guard.end) else {
+        return Ok(());
+    };
+    let mut start = guard.end;
+    let mut end = min(
+        guard.end + STACK_OVERFLOW_CAPTURE_LEN,
+        stack_mapping.end_address(),
+    );
+    if thread.stack.memory.data_size != 0 {
+        let stack_start = thread.stack.start_of_memory_range as usize;
+        let stack_end = stack_start + thread.stack.memory.data_size as usize;
+        if stack_start <= start {
+            start = start.max(stack_end);
+        } else {
+            end = end.min(stack_start);
+        }
+    }
+    if start >= end {
+        return Ok(());
+    }
+
+    let mut stack_bytes = PtraceDumper::copy_from_process(tid, start, end - start)?;
+    if config.sanitize_stack {
+        config.sanitize_memory(
+            dumper,
+            SanitizedRegionKind::Stack,
+            &mut stack_bytes,
+            start,
+            start,
+            0,
+        )?;
+    }
+    let mem_section = MemoryArrayWriter::alloc_from_array(buffer, &stack_bytes)?;
+    config.memory_blocks.push(MDMemoryDescriptor {
+        start_of_memory_range: start as u64,
+        memory: mem_section.location(),
+    });
+    Ok(())
+}
+
+/// Include the stack of the signal handler the thread is running, from its
+/// stack pointer to the end of the signal frame, unless the stack of the
+/// thread already covers it.
+fn write_handler_stack(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
+    thread: &MDRawThread,
+    tid: Pid,
+    handler_stack_ptr: usize,
+    frame: &SignalFrame,
+) -> Result<(), errors::SectionThreadListError> {
+    let stack_start = thread.stack.start_of_memory_range as usize;
+    let stack_end = stack_start + thread.stack.memory.data_size as usize;
+    if thread.stack.memory.data_size != 0
+        && stack_start <= handler_stack_ptr
+        && handler_stack_ptr < stack_end
+    {
+        return Ok(());
+    }
+
+    let mut stack_bytes = PtraceDumper::copy_from_process(
+        tid,
+        handler_stack_ptr,
+        frame.end_address - handler_stack_ptr,
+    )?;
+    if config.sanitize_stack {
+        config.sanitize_memory(
+            dumper,
+            SanitizedRegionKind::Stack,
+            &mut stack_bytes,
+            handler_stack_ptr,
+            handler_stack_ptr,
+            0,
+        )?;
+    }
+    let mem_section = MemoryArrayWriter::alloc_from_array(buffer, &stack_bytes)?;
+    config.memory_blocks.push(MDMemoryDescriptor {
+        start_of_memory_range: handler_stack_ptr as u64,
+        memory: mem_section.location(),
+    });
+    Ok(())
+}
+
+/// Include the alternate signal stack the signal handler of the crashing
+/// thread runs on, from the crash context, from the stack pointer of the
+/// handler up. The crash context only covers the stack the signal interrupted.
+/// Nothing is included if the handler doesn't run on the alternate stack.
+fn write_signal_stack(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
+    thread: &MDRawThread,
+    idx: usize,
+    tid: Pid,
+) -> Result<(), errors::SectionThreadListError> {
+    let uc_stack = &config
+        .crash_context
+        .as_ref()
+        .unwrap()
+        .inner
+        .context
+        .uc_stack;
+    let (ss_sp, ss_flags, ss_size) = (uc_stack.ss_sp as usize, uc_stack.ss_flags, uc_stack.ss_size);
+    if ss_flags & libc::SS_DISABLE != 0 || ss_size == 0 {
+        return Ok(());
+    }
+    let altstack = ss_sp..ss_sp.saturating_add(ss_size);
+    let Some(handler_stack_ptr) = dumper
+        .get_thread_info_by_index(idx)
+        .ok()
+        .map(|info| info.stack_pointer)
+        .filter(|stack_ptr| altstack.contains(stack_ptr))
+    else {
+        return Ok(());
+    };
+
+    let mut max_len = MAX_SIGNAL_STACK_LEN;
+    if let Some(minidump_size_limit) = config.minidump_size_limit {
+        let available =
+            minidump_size_limit.saturating_sub(buffer.position() + LIMIT_MINIDUMP_FUDGE_FACTOR);
+        max_len = max_len.min(available as usize);
+    }
+    let range = handler_stack_ptr..altstack.end.min(handler_stack_ptr.saturating_add(max_len));
+
+    let mut signal_stack = MDRawSignalStack {
+        thread_id: config.reported_thread_id(dumper, tid).try_into()?,
+        flags: ss_flags as u32,
+        ss_sp: altstack.start as u64,
+        ss_size: altstack.len() as u64,
+        handler_stack_pointer: handler_stack_ptr as u64,
+        start_of_memory_range: range.start as u64,
+        ..Default::default()
+    };
+
+    let stack_start = thread.stack.start_of_memory_range as usize;
+    let stack_end = stack_start + thread.stack.memory.data_size as usize;
+    let covered =
+        thread.stack.memory.data_size != 0 && range.start < stack_end && stack_start < range.end;
+    if !covered && !range.is_empty() {
+        if let Ok(mut stack_bytes) = PtraceDumper::copy_from_process(tid, range.start, range.len())
+        {
+            if config.sanitize_stack {
+                config.sanitize_memory(
+                    dumper,
+                    SanitizedRegionKind::Stack,
+                    &mut stack_bytes,
+                    range.start,
+                    range.start,
+                    0,
+                )?;
+            }
+            let mem_section = MemoryArrayWriter::alloc_from_array(buffer, &stack_bytes)?;
+            let memory = MDMemoryDescriptor {
+                start_of_memory_range: range.start as u64,
+                memory: mem_section.location(),
+            };
+            config.memory_blocks.push(memory);
+            signal_stack.data_size = memory.memory.data_size;
+            signal_stack.rva = memory.memory.rva;
+        }
+    }
+    config.signal_stack = Some(signal_stack);
+    Ok(())
+}
+
+/// The windows of readable memory around the register values in `cpu`, and
+/// around `fault_address`, according to the register memory policy.
+fn find_register_windows(
+    config: &MinidumpWriter,
+    dumper: &PtraceDumper,
+    cpu: &RawContextCPU,
+    fault_address: Option<usize>,
+) -> Vec<Range<usize>> {
+    let Some(policy) = &config.register_memory_policy else {
+        return Vec::new();
+    };
+    register_memory::register_values(cpu)
+        .into_iter()
+        .chain(fault_address.map(|address| (RegisterClass::FaultAddress, address)))
+        .filter_map(|(class, value)| readable_window(dumper, value, policy.window(class, value)))
+        .collect()
+}
+
+/// `window` bounded by the mapping `value` is in, if it is readable.
+fn readable_window(
+    dumper: &PtraceDumper,
+    value: usize,
+    window: Range<usize>,
+) -> Option<Range<usize>> {
+    let mapping = dumper
+        .find_mapping(value)
+        .filter(|mapping| mapping.is_readable())?;
+    let window = window.start.max(mapping.start_address)..window.end.min(mapping.end_address());
+    (!window.is_empty()).then_some(window)
+}
+
+/// Include the memory `windows` in the minidump, except for the memory that is
+/// already in it, e.g. the stacks. Windows that can't be read, or that would
+/// exceed the minidump size limit, are skipped.
+fn write_memory_windows(
+    config: &mut MinidumpWriter,
+    buffer: &mut DumpBuf,
+    dumper: &PtraceDumper,
+    windows: Vec<Range<usize>>,
+    kind: SanitizedRegionKind,
+) -> Result<(), errors::SectionThreadListError> {
+    let covered: Vec<Range<usize>> = config
+        .memory_blocks
+        .iter()
+        .map(|block| {
+            let start = block.start_of_memory_range as usize;
+            start..start + block.memory.data_size as usize
+        })
+        .collect();
+
+    for window in register_memory::merge_and_subtract(windows, &covered) {
+        if let Some(minidump_size_limit) = config.minidump_size_limit {
+            let available =
+                minidump_size_limit.saturating_sub(buffer.position() + LIMIT_MINIDUMP_FUDGE_FACTOR);
+            if window.len() as u64 > available {
+                continue;
+            }
+        }
+        let Ok(mut memory) =
+            PtraceDumper::copy_from_process(config.process_id, window.start, window.len())
+        else {
+            continue;
+        };
+        if config.sanitize_stack {
+            config.sanitize_memory(dumper, kind, &mut memory, window.start, window.start, 0)?;
+        }
+        let mem_section = MemoryArrayWriter::alloc_from_array(buffer, &memory)?;
+        config.memory_blocks.push(MDMemoryDescriptor {
+            start_of_memory_range: window.start as u64,
+            memory: mem_section.location(),
+        });
+    }
+    Ok(())
+}
@@ -1,6 +1,7 @@
 use super::*;
 
 pub fn write(
+    config: &MinidumpWriter,
     buffer: &mut DumpBuf,
     dumper: &PtraceDumper,
 ) -> Result<MDRawDirectory, errors::SectionThreadNamesError> {
@@ -23,7 +24,7 @@ pub fn write(
         if let Some(name) = &item.name {
             let pos = write_string_to_location(buffer, name)?;
             let thread = MDRawThreadName {
-                thread_id: item.tid.try_into()?,
+                thread_id: config.reported_thread_id(dumper, item.tid).try_into()?,
                 thread_name_rva: pos.rva.into(),
             };
             thread_list.set_value_at(buffer, thread, idx)?;
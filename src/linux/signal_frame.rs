@@ -0,0 +1,210 @@
+//! Recovery of the registers a thread had when a signal interrupted it, from
+//! the `rt_sigframe` the kernel pushed on the stack of the signal handler.
+//!
+//! The frame is found by looking above the stack pointer for the return
+//! address of the handler, the signal trampoline (`__restore_rt`), which is
+//! recognized by its code rather than its symbol so that it's found in libc
+//! and the vDSO alike. The frame is only used if its floating point state and
+//! its alternate signal stack are where the kernel puts them, otherwise the
+//! registers of the thread are kept. This is only implemented for x86_64.
+
+#[cfg(target_arch = "x86_64")]
+use crate::linux::maps_reader::MappingInfo;
+use crate::{
+    linux::{crash_context::CrashContext, ptrace_dumper::PtraceDumper},
+    Pid,
+};
+
+/// How far above the stack pointer to look for a signal frame.
+#[cfg(target_arch = "x86_64")]
+const MAX_SIGNAL_FRAME_DISTANCE: usize = 64 * 1024;
+
+/// `mov $__NR_rt_sigreturn, %rax; syscall`
+#[cfg(target_arch = "x86_64")]
+const RT_SIGRETURN_CODE: &[u8] = &[0x48, 0xc7, 0xc0, 0x0f, 0x00, 0x00, 0x00, 0x0f, 0x05];
+
+/// The size of the kernel's `struct ucontext`, which the `siginfo_t` of the
+/// signal follows in the frame.
+#[cfg(target_arch = "x86_64")]
+const KERNEL_UCONTEXT_SIZE: usize = 304;
+/// The size of `siginfo_t`.
+#[cfg(target_arch = "x86_64")]
+const SIGINFO_SIZE: usize = 128;
+/// The alignment of the floating point state in the frame.
+#[cfg(target_arch = "x86_64")]
+const FPSTATE_ALIGN: usize = 64;
+
+/// A signal frame on the stack of a thread.
+pub struct SignalFrame {
+    /// The address of the frame, where the return address of the handler is
+    pub address: usize,
+    /// The end of the frame, not counting the floating point state
+    pub end_address: usize,
+    /// The signal the handler was called for, 0 if the handler wasn't
+    /// installed with `SA_SIGINFO`, as the kernel only writes the
+    /// `siginfo_t` then
+    pub signal: u32,
+    /// The registers of the thread when the signal interrupted it
+    pub context: CrashContext,
+}
+
+/// Find the innermost signal frame above `stack_pointer` in the stack of
+/// thread `tid`, if it is running a signal handler.
+#[cfg(target_arch = "x86_64")]
+pub(crate) fn find_signal_frame(
+    dumper: &PtraceDumper,
+    tid: Pid,
+    stack_pointer: usize,
+) -> Option<SignalFrame> {
+    use libc::{REG_RIP, REG_RSP};
+
+    const WORD: usize = std::mem::size_of::<usize>();
+
+    let start = stack_pointer & !(WORD - 1);
+    let mapping = dumper.find_mapping(start)?;
+    let end = mapping
+        .end_address()
+        .min(start.saturating_add(MAX_SIGNAL_FRAME_DISTANCE));
+    let stack = PtraceDumper::copy_from_process(tid, start, end - start).ok()?;
+
+    let is_code = |address: usize| {
+        dumper
+            .find_mapping(address)
+            .is_some_and(|mapping| mapping.is_executable())
+    };
+    let mut checked = Vec::new();
+    for (offset, word) in stack.chunks_exact(WORD).enumerate() {
+        let return_address = usize::from_ne_bytes(word.try_into().unwrap());
+        if checked.contains(&return_address) || !is_code(return_address) {
+            continue;
+        }
+        let is_trampoline =
+            PtraceDumper::copy_from_process(tid, return_address, RT_SIGRETURN_CODE.len())
+                .is_ok_and(|code| code == RT_SIGRETURN_CODE);
+        if !is_trampoline {
+            checked.push(return_address);
+            continue;
+        }
+
+        let address = start + offset * WORD;
+        let Some(frame) = read_signal_frame(tid, address) else {
+            continue;
+        };
+        let ucontext = &frame.context.inner.context;
+        let gregs = &ucontext.uc_mcontext.gregs;
+        let interrupted_sp = gregs[REG_RSP as usize] as usize;
+        if !is_code(gregs[REG_RIP as usize] as usize)
+            || !is_fpstate_of(mapping, &frame, ucontext.uc_mcontext.fpregs as usize)
+            || !is_on_signal_stack(mapping, address, interrupted_sp, &ucontext.uc_stack)
+        {
+            continue;
+        }
+        return Some(frame);
+    }
+    None
+}
+
+/// The kernel puts the floating point state right above the frame, aligned to
+/// 64 bytes, on the same stack.
+#[cfg(target_arch = "x86_64")]
+fn is_fpstate_of(stack: &MappingInfo, frame: &SignalFrame, fpregs: usize) -> bool {
+    fpregs.is_multiple_of(FPSTATE_ALIGN)
+        && (frame.end_address..frame.end_address + FPSTATE_ALIGN).contains(&fpregs)
+        && stack.contains_address(fpregs)
+}
+
+/// Whether the frame at `address` is where the kernel puts the frame of a
+/// signal interrupting code with the stack pointer `interrupted_sp`, given the
+/// alternate signal stack of the thread the kernel saved in the frame: on the
+/// alternate signal stack, or below `interrupted_sp` on the same stack.
+#[cfg(target_arch = "x86_64")]
+fn is_on_signal_stack(
+    stack: &MappingInfo,
+    address: usize,
+    interrupted_sp: usize,
+    uc_stack: &crash_context::stack_t,
+) -> bool {
+    let below_interrupted_sp = address < interrupted_sp && stack.contains_address(interrupted_sp);
+    if uc_stack.ss_flags & libc::SS_DISABLE != 0 {
+        return uc_stack.ss_size == 0 && below_interrupted_sp;
+    }
+    let ss_sp = uc_stack.ss_sp as usize;
+    let altstack = ss_sp..ss_sp.saturating_add(uc_stack.ss_size);
+    if altstack.is_empty() {
+        return false;
+    }
+    // `SS_ONSTACK` tells whether the interrupted code was on the alternate
+    // signal stack already, the frame is then below it on that stack
+    if uc_stack.ss_flags & libc::SS_ONSTACK != 0 {
+        return altstack.contains(&interrupted_sp)
+            && altstack.contains(&address)
+            && below_interrupted_sp;
+    }
+    !altstack.contains(&interrupted_sp) && (altstack.contains(&address) || below_interrupted_sp)
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub(crate) fn find_signal_frame(
+    _dumper: &PtraceDumper,
+    _tid: Pid,
+    _stack_pointer: usize,
+) -> Option<SignalFrame> {
+    None
+}
+
+/// Read the `rt_sigframe` at `address`: the return address, the kernel's
+/// `struct ucontext` and the `siginfo_t`. The floating point state is
+/// elsewhere on the stack, `fpregs` points to it.
+#[cfg(target_arch = "x86_64")]
+fn read_signal_frame(tid: Pid, address: usize) -> Option<SignalFrame> {
+    // The kernel's `struct ucontext` matches the start of `ucontext_t`, up to
+    // the signal mask which is much smaller.
+    let ucontext_address = address + std::mem::size_of::<usize>();
+    let prefix_len = memoffset::offset_of!(crash_context::ucontext_t, uc_sigmask);
+    let prefix = PtraceDumper::copy_from_process(tid, ucontext_address, prefix_len).ok()?;
+    let signal = PtraceDumper::copy_from_process(tid, ucontext_address + KERNEL_UCONTEXT_SIZE, 4)
+        .ok()
+        .map(|signo| u32::from_ne_bytes(signo.try_into().unwrap()))?;
+
+    // Safety: all of these are plain C structs, for which zeroes are valid
+    let mut context: crash_context::ucontext_t = unsafe { std::mem::zeroed() };
+    let mut float_state: crash_context::fpregset_t = unsafe { std::mem::zeroed() };
+    let mut siginfo: libc::signalfd_siginfo = unsafe { std::mem::zeroed() };
+    // Safety: `prefix_len` bytes of the context are overwritten
+    unsafe {
+        std::ptr::copy_nonoverlapping(
+            prefix.as_ptr(),
+            std::ptr::addr_of_mut!(context).cast::<u8>(),
+            prefix_len,
+        );
+    }
+
+    let fpregs = context.uc_mcontext.fpregs as usize;
+    if fpregs != 0 {
+        if let Ok(data) = PtraceDumper::copy_from_process(
+            tid,
+            fpregs,
+            std::mem::size_of::<crash_context::fpregset_t>(),
+        ) {
+            // Safety: `copy_from_process` returns exactly as many bytes as
+            // requested
+            float_state = unsafe { std::ptr::read_unaligned(data.as_ptr().cast()) };
+        }
+    }
+    siginfo.ssi_signo = signal;
+
+    Some(SignalFrame {
+        address,
+        end_address: ucontext_address + KERNEL_UCONTEXT_SIZE + SIGINFO_SIZE,
+        signal,
+        context: CrashContext {
+            inner: crash_context::CrashContext {
+                context,
+                float_state,
+                siginfo,
+                pid: 0,
+                tid,
+            },
+        },
+    })
+}
@@ -33,6 +33,7 @@ enum NT_Elf {
     //NT_TASKSTRUCT = 4,
     //NT_AUXV = 6,
     NT_ARM_VFP = 0x400, // ARM VFP/NEON registers
+    NT_ARM_TLS = 0x401, // ARM TLS register
 }
 
 #[inline]
@@ -21,6 +21,8 @@ type Result<T> = std::result::Result<T, ThreadInfoError>;
 #[derive(Debug)]
 pub struct ThreadInfoAarch64 {
     pub stack_pointer: usize,
+    /// The value of `TPIDR_EL0`, which points to the thread control block
+    pub thread_pointer: usize,
     pub tgid: Pid, // thread group id
     pub ppid: Pid, // parent process
     pub regs: libc::user_regs_struct,
@@ -70,6 +72,15 @@ impl ThreadInfoAarch64 {
         )
     }
 
+    // nix currently doesn't support PTRACE_GETREGSET, so we have to do it ourselves
+    fn gettls(pid: Pid) -> Result<u64> {
+        Self::ptrace_get_data_via_io(
+            0x4204 as ptrace::RequestType, // PTRACE_GETREGSET
+            Some(NT_Elf::NT_ARM_TLS),
+            nix::unistd::Pid::from_raw(pid),
+        )
+    }
+
     pub fn fill_cpu_context(&self, out: &mut RawContextCPU) {
         out.context_flags =
             minidump_common::format::ContextFlagsArm64Old::CONTEXT_ARM64_OLD_FULL.bits() as u64;
@@ -93,9 +104,11 @@ impl ThreadInfoAarch64 {
         let fpregs = Self::getfpregset(tid).or_else(|_| Self::getfpregs(tid))?;
 
         let stack_pointer = regs.sp as usize;
+        let thread_pointer = Self::gettls(tid).unwrap_or(0) as usize;
 
         Ok(Self {
             stack_pointer,
+            thread_pointer,
             tgid,
             ppid,
             regs,
@@ -22,6 +22,8 @@ pub struct user_regs_struct {
 #[derive(Debug)]
 pub struct ThreadInfoArm {
     pub stack_pointer: usize,
+    /// The value of `TPIDRURO`, which points to the thread control block
+    pub thread_pointer: usize,
     pub tgid: Pid, // thread group id
     pub ppid: Pid, // parent process
     pub regs: user_regs_struct,
@@ -49,6 +51,15 @@ impl ThreadInfoArm {
         )
     }
 
+    // nix currently doesn't support PTRACE_GET_THREAD_AREA, so we have to do it ourselves
+    fn get_thread_area(pid: Pid) -> Result<u32> {
+        Self::ptrace_get_data::<u32>(
+            22 as ptrace::RequestType, // PTRACE_GET_THREAD_AREA
+            None,
+            nix::unistd::Pid::from_raw(pid),
+        )
+    }
+
     pub fn get_instruction_pointer(&self) -> usize {
         self.regs.uregs[15] as usize
     }
@@ -69,9 +80,11 @@ impl ThreadInfoArm {
         let fpregs = Self::getfpregs(tid).unwrap_or(Default::default());
 
         let stack_pointer = regs.uregs[13] as usize;
+        let thread_pointer = Self::get_thread_area(tid).unwrap_or(0) as usize;
 
         Ok(ThreadInfoArm {
             stack_pointer,
+            thread_pointer,
             tgid,
             ppid,
             regs,
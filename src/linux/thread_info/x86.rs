@@ -94,6 +94,9 @@ const NUM_DEBUG_REGISTERS: usize = 8;
 
 pub struct ThreadInfoX86 {
     pub stack_pointer: usize,
+    /// The base of the `fs` (x86_64) or `gs` (x86) segment, which points to
+    /// the thread control block
+    pub thread_pointer: usize,
     pub tgid: Pid, // thread group id
     pub ppid: Pid, // parent process
     pub regs: user_regs_struct,
@@ -155,6 +158,23 @@ impl ThreadInfoX86 {
         )
     }
 
+    // nix currently doesn't support PTRACE_GET_THREAD_AREA, so we have to do it ourselves
+    #[cfg(target_arch = "x86")]
+    fn get_thread_area(pid: Pid, entry: u32) -> Result<u32> {
+        // A `struct user_desc`, the base address is its second field
+        let mut desc = [entry, 0, 0, 0];
+        let res = unsafe {
+            libc::ptrace(
+                25 as ptrace::RequestType, // PTRACE_GET_THREAD_AREA
+                pid,
+                entry as usize,
+                desc.as_mut_ptr(),
+            )
+        };
+        nix::errno::Errno::result(res)?;
+        Ok(desc[1])
+    }
+
     fn peek_user(pid: Pid, addr: ptrace::AddressType) -> nix::Result<libc::c_long> {
         Self::ptrace_peek(
             ptrace::Request::PTRACE_PEEKUSER as ptrace::RequestType,
@@ -206,8 +226,15 @@ impl ThreadInfoX86 {
         #[cfg(target_arch = "x86")]
         let stack_pointer = regs.esp as usize;
 
+        #[cfg(target_arch = "x86_64")]
+        let thread_pointer = regs.fs_base as usize;
+        // The segment selector indexes the GDT entry of the TLS segment
+        #[cfg(target_arch = "x86")]
+        let thread_pointer = Self::get_thread_area(tid, regs.xgs as u32 >> 3).unwrap_or(0) as usize;
+
         Ok(Self {
             stack_pointer,
+            thread_pointer,
             tgid,
             ppid,
             regs,
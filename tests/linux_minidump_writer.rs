@@ -75,6 +75,45 @@ fn get_crash_context(tid: Pid) -> CrashContext {
     }
 }
 
+/// Read the `MINIDUMP_STRING` at `rva` in the contents of a minidump file.
+fn read_minidump_string(data: &[u8], rva: u32) -> String {
+    use scroll::Pread;
+
+    let rva = rva as usize;
+    let length: u32 = data.pread_with(rva, scroll::LE).unwrap();
+    let units: Vec<u16> = (0..length as usize / 2)
+        .map(|i| data.pread_with(rva + 4 + i * 2, scroll::LE).unwrap())
+        .collect();
+    String::from_utf16(&units).unwrap()
+}
+
+/// Read the entries of the `LinuxModuleInfo` stream, in the order of the
+/// module list.
+fn read_module_infos<'a, T>(
+    dump: &Minidump<'a, T>,
+) -> Vec<minidump_writer::ext_streams::MDRawModuleInfo>
+where
+    T: std::ops::Deref<Target = [u8]> + 'a,
+{
+    use minidump_writer::ext_streams::{ExtStreamType, MDRawModuleInfo, MDRawModuleInfoList};
+    use scroll::Pread;
+
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxModuleInfo.into())
+        .expect("missing module info stream");
+    let header: MDRawModuleInfoList = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(
+        header.size_of_entry as usize,
+        std::mem::size_of::<MDRawModuleInfo>()
+    );
+    (0..header.number_of_entries as usize)
+        .map(|i| {
+            let offset = header.size_of_header as usize + i * header.size_of_entry as usize;
+            stream.pread_with(offset, scroll::LE).unwrap()
+        })
+        .collect()
+}
+
 macro_rules! contextual_test {
     ( $(#[$attr:meta])? fn $name:ident ($ctx:ident : Context) $body:block ) => {
         mod $name {
@@ -162,6 +201,10 @@ contextual_test! {
             offset: 0,
             permissions: MMPermissions::READ | MMPermissions::WRITE,
             name: Some("a fake mapping".into()),
+            deleted: false,
+            device: (0, 0),
+            inode: 0,
+            first_mapping_end: 0,
             system_mapping_info: SystemMappingInfo {
                 start_address: mmap_addr,
                 end_address: mmap_addr + memory_size,
@@ -781,6 +824,19 @@ fn with_deleted_binary() {
 
     // The 'age'/appendix, always 0 on non-windows targets
     assert_eq!(did.appendix(), 0);
+
+    // The module is marked as backed by a deleted file
+    {
+        use minidump_writer::ext_streams::MODULE_FLAG_FILE_DELETED;
+
+        let infos = read_module_infos(&dump);
+        assert_eq!(infos.len(), module_list.iter().count());
+        let main_module_info = infos
+            .into_iter()
+            .find(|info| info.base_of_image == main_module.raw.base_of_image)
+            .expect("missing info of the main module");
+        assert_ne!(main_module_info.flags & MODULE_FLAG_FILE_DELETED, 0);
+    }
 }
 
 #[test]
@@ -805,3 +861,1735 @@ fn memory_info_list_stream() {
     let list: MinidumpMemoryInfoList = dump.get_stream().expect("no memory info list");
     assert!(list.iter().count() > 1);
 }
+
+#[test]
+fn smaps_stream() {
+    use minidump_writer::{
+        ext_streams::{ExtStreamType, MDRawSmapsEntry, MDRawSmapsList},
+        minidump_writer::SmapsMode,
+    };
+    use scroll::Pread;
+
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    for (mode, stream_type) in [
+        (SmapsMode::Mappings, ExtStreamType::LinuxSmaps),
+        (SmapsMode::Rollup, ExtStreamType::LinuxSmapsRollup),
+    ] {
+        let mut tmpfile = tempfile::Builder::new()
+            .prefix("smaps_stream")
+            .tempfile()
+            .unwrap();
+
+        MinidumpWriter::new(pid, pid)
+            .set_smaps_mode(mode)
+            .dump(&mut tmpfile)
+            .expect("cound not write minidump");
+
+        let dump = Minidump::read_path(tmpfile.path()).expect("failed to read minidump");
+        let stream = dump
+            .get_raw_stream(stream_type.into())
+            .expect("missing smaps stream");
+        let header: MDRawSmapsList = stream.pread_with(0, scroll::LE).unwrap();
+        assert_eq!(
+            header.size_of_entry as usize,
+            std::mem::size_of::<MDRawSmapsEntry>()
+        );
+
+        let entries: Vec<MDRawSmapsEntry> = (0..header.number_of_entries as usize)
+            .map(|i| {
+                let offset = header.size_of_header as usize + i * header.size_of_entry as usize;
+                stream.pread_with(offset, scroll::LE).unwrap()
+            })
+            .collect();
+
+        if mode == SmapsMode::Rollup {
+            assert_eq!(entries.len(), 1);
+        } else {
+            assert!(entries.len() > 1);
+        }
+        // At least the main executable and the stack have to be resident.
+        assert!(entries.iter().any(|e| e.rss > 0));
+        assert!(entries.iter().all(|e| e.rss <= e.region_size));
+    }
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+}
+
+#[test]
+fn memory_info_list_stream_groups_stack_guards() {
+    use minidump_common::format::MemoryState;
+
+    // The second thread's stack is mapped with a guard page below it
+    let mut child = start_child_and_wait_for_threads(2);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("memory_info_list_stream_groups_stack_guards")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .dump(&mut tmpfile)
+        .expect("cound not write minidump");
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("failed to read minidump");
+    let list: MinidumpMemoryInfoList = dump.get_stream().expect("no memory info list");
+    let infos: Vec<_> = list.iter().map(|info| &info.raw).collect();
+
+    assert!(infos.windows(2).any(|pair| {
+        pair[0].state == MemoryState::MEM_RESERVE.bits()
+            && pair[1].state == MemoryState::MEM_COMMIT.bits()
+            && pair[0].base_address + pair[0].region_size == pair[1].base_address
+            && pair[1].allocation_base == pair[0].base_address
+    }));
+}
+
+#[test]
+fn handle_data_stream_object_information() {
+    use minidump_common::format::{
+        MINIDUMP_HANDLE_DATA_STREAM, MINIDUMP_HANDLE_OBJECT_INFORMATION,
+    };
+    use minidump_writer::ext_streams::{
+        HandleObjectInfoKind, MDRawFdInfo, MDRawHandleDescriptor2, MDRawSocketInfo,
+    };
+    use scroll::Pread;
+
+    let mut child = start_child_and_return(&["create_sockets_wait"]);
+    wait_for_threads(&mut child, 1);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("handle_data_stream_object_information")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .dump(&mut tmpfile)
+        .expect("cound not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("failed to read minidump");
+    let handles: MinidumpHandleDataStream = dump.get_stream().expect("missing handle stream");
+    let sockets: Vec<_> = handles
+        .handles
+        .iter()
+        .filter(|handle| {
+            handle
+                .object_name
+                .as_deref()
+                .unwrap_or("")
+                .starts_with("socket:")
+        })
+        .collect();
+    assert!(!sockets.is_empty(), "missing socket descriptor");
+    for socket in &sockets {
+        assert_eq!(socket.type_name.as_deref(), Some("socket"));
+    }
+
+    // Walk the object information chain of the sockets in the raw stream
+    let stream = dump
+        .get_raw_stream(HandleDataStream.into())
+        .expect("missing handle stream");
+    let header: MINIDUMP_HANDLE_DATA_STREAM = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(
+        header.size_of_descriptor as usize,
+        std::mem::size_of::<MDRawHandleDescriptor2>()
+    );
+    let data = std::fs::read(tmpfile.path()).unwrap();
+    let data = data.as_slice();
+
+    // The child may have inherited other sockets, look for the TCP listener
+    let (fd_info, socket_info) = (0..header.number_of_descriptors as usize)
+        .map(|i| {
+            let offset = header.size_of_header as usize + i * header.size_of_descriptor as usize;
+            stream
+                .pread_with::<MDRawHandleDescriptor2>(offset, scroll::LE)
+                .unwrap()
+        })
+        .filter(|descriptor| {
+            sockets
+                .iter()
+                .any(|socket| Some(&descriptor.handle) == socket.raw.handle())
+        })
+        .map(|descriptor| {
+            let mut fd_info = None;
+            let mut socket_info = None;
+            let mut rva = descriptor.object_info_rva as usize;
+            while rva != 0 {
+                let info: MINIDUMP_HANDLE_OBJECT_INFORMATION =
+                    data.pread_with(rva, scroll::LE).unwrap();
+                let payload = rva + std::mem::size_of::<MINIDUMP_HANDLE_OBJECT_INFORMATION>();
+                let kind: u32 = data.pread_with(payload, scroll::LE).unwrap();
+                if kind == HandleObjectInfoKind::FdInfo as u32 {
+                    fd_info = Some(data.pread_with::<MDRawFdInfo>(payload, scroll::LE).unwrap());
+                } else if kind == HandleObjectInfoKind::Socket as u32 {
+                    socket_info = Some(
+                        data.pread_with::<MDRawSocketInfo>(payload, scroll::LE)
+                            .unwrap(),
+                    );
+                }
+                rva = info.next_info_rva as usize;
+            }
+            (fd_info, socket_info)
+        })
+        .find(|(_, socket_info)| {
+            socket_info.is_some_and(|info| info.family == libc::AF_INET as u16)
+        })
+        .expect("missing socket information");
+
+    let fd_info = fd_info.expect("missing fdinfo");
+    assert_eq!(fd_info.flags & libc::O_ACCMODE as u32, libc::O_RDWR as u32);
+    let socket_info = socket_info.unwrap();
+    assert_eq!(socket_info.socket_type, libc::SOCK_STREAM as u16);
+    // TCP_LISTEN
+    assert_eq!(socket_info.state, 10);
+    assert_eq!(socket_info.local_address[..4], [127, 0, 0, 1]);
+    assert_ne!(socket_info.local_port, 0);
+}
+
+#[test]
+fn container_info_stream() {
+    use minidump_writer::ext_streams::{ExtStreamType, MDRawCgroupEntry, MDRawContainerInfo};
+    use scroll::Pread;
+
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("container_info_stream")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .dump(&mut tmpfile)
+        .expect("cound not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("failed to read minidump");
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxContainerInfo.into())
+        .expect("missing container info stream");
+    let info: MDRawContainerInfo = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(
+        info.size_of_cgroup as usize,
+        std::mem::size_of::<MDRawCgroupEntry>()
+    );
+    assert!(info.number_of_cgroups > 0);
+    assert_eq!(
+        stream.len(),
+        info.size_of_header as usize
+            + info.number_of_cgroups as usize * info.size_of_cgroup as usize
+    );
+
+    // The child runs in our namespaces
+    assert_eq!(info.ns_pid, pid as u32);
+    let own_net_ns = std::fs::read_link("/proc/self/ns/net").unwrap();
+    assert_eq!(
+        own_net_ns.to_str().unwrap(),
+        format!("net:[{}]", info.net_ns)
+    );
+}
+
+#[test]
+fn security_context_stream() {
+    use minidump_writer::ext_streams::{ExtStreamType, MDRawSecurityContext};
+    use scroll::Pread;
+
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("security_context_stream")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .dump(&mut tmpfile)
+        .expect("cound not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("failed to read minidump");
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxSecurityContext.into())
+        .expect("missing security context stream");
+    let context: MDRawSecurityContext = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(
+        stream.len(),
+        context.size_of_header as usize + context.number_of_groups as usize * 4
+    );
+
+    // The child inherits our credentials and sandbox
+    let own_status = std::fs::read_to_string("/proc/self/status").unwrap();
+    let own_value = |key: &str| {
+        own_status
+            .lines()
+            .find_map(|line| line.strip_prefix(key))
+            .map(|value| value.trim().to_owned())
+            .unwrap()
+    };
+    assert_eq!(context.real_uid, unsafe { libc::getuid() });
+    assert_eq!(context.effective_gid, unsafe { libc::getegid() });
+    assert_eq!(
+        context.cap_bounding,
+        u64::from_str_radix(&own_value("CapBnd:"), 16).unwrap()
+    );
+    assert_eq!(
+        context.seccomp_mode,
+        own_value("Seccomp:").parse::<u32>().unwrap()
+    );
+
+    let groups: Vec<u32> = (0..context.number_of_groups as usize)
+        .map(|i| {
+            stream
+                .pread_with(context.size_of_header as usize + i * 4, scroll::LE)
+                .unwrap()
+        })
+        .collect();
+    let own_groups: Vec<u32> = own_value("Groups:")
+        .split_whitespace()
+        .map(|g| g.parse().unwrap())
+        .collect();
+    assert_eq!(groups, own_groups);
+}
+
+#[test]
+fn thread_id_namespace() {
+    use minidump_writer::minidump_writer::ThreadIdNamespace;
+
+    let mut child = start_child_and_wait_for_named_threads(2);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("thread_id_namespace")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .set_thread_id_namespace(ThreadIdNamespace::Process)
+        .dump(&mut tmpfile)
+        .expect("cound not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    // The child is in our pid namespace, so the ids are the same in both
+    let dump = Minidump::read_path(tmpfile.path()).expect("failed to read minidump");
+    let threads: MinidumpThreadList = dump.get_stream().expect("missing thread list");
+    let names: MinidumpThreadNames = dump.get_stream().expect("missing thread names");
+    let exception: MinidumpException = dump.get_stream().expect("missing exception");
+
+    assert_eq!(exception.get_crashing_thread_id(), pid as u32);
+    assert!(threads
+        .threads
+        .iter()
+        .any(|t| t.raw.thread_id == pid as u32));
+    for thread in &threads.threads {
+        assert!(names.get_name(thread.raw.thread_id).is_some());
+    }
+}
+
+#[cfg(not(target_arch = "mips"))]
+#[test]
+fn siginfo_exception_information() {
+    use minidump_writer::ext_streams::SiginfoIndex;
+
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("siginfo_exception_information")
+        .tempfile()
+        .unwrap();
+
+    // A system call blocked by seccomp
+    let mut crash_context = get_crash_context(pid);
+    let siginfo = &mut crash_context.inner.siginfo;
+    siginfo.ssi_signo = libc::SIGSYS as u32;
+    siginfo.ssi_code = 1; // SYS_SECCOMP
+    siginfo.ssi_errno = -1;
+    siginfo.ssi_pid = 1234;
+    siginfo.ssi_uid = 1000;
+    siginfo.ssi_syscall = libc::SYS_ptrace as i32;
+    siginfo.ssi_call_addr = 0x7f00_0000_1234;
+    siginfo.ssi_arch = 0xc000_003e;
+
+    MinidumpWriter::new(pid, pid)
+        .set_crash_context(crash_context)
+        .dump(&mut tmpfile)
+        .expect("cound not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("failed to read minidump");
+    let exception: MinidumpException = dump.get_stream().expect("missing exception");
+    let record = &exception.raw.exception_record;
+    assert_eq!(record.exception_code, libc::SIGSYS as u32);
+    assert_eq!(record.exception_flags, 1);
+    assert_eq!(record.number_parameters, 15);
+
+    let parameter = |index: SiginfoIndex| record.exception_information[index as usize];
+    assert_eq!(parameter(SiginfoIndex::Errno) as i64, -1);
+    assert_eq!(parameter(SiginfoIndex::Pid), 1234);
+    assert_eq!(parameter(SiginfoIndex::Uid), 1000);
+    assert_eq!(parameter(SiginfoIndex::Syscall), libc::SYS_ptrace as u64);
+    assert_eq!(parameter(SiginfoIndex::CallAddr), 0x7f00_0000_1234);
+    assert_eq!(parameter(SiginfoIndex::Arch), 0xc000_003e);
+    assert_eq!(parameter(SiginfoIndex::AddrLsb), 0);
+}
+
+#[test]
+fn dump_reason() {
+    use minidump_writer::{
+        ext_streams::{ExtStreamType, MDRawDumpReason},
+        minidump_writer::DumpReason,
+    };
+    use scroll::Pread;
+
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("dump_reason")
+        .tempfile()
+        .unwrap();
+
+    let parameters: Vec<u64> = (1..=20).collect();
+    MinidumpWriter::new(pid, pid)
+        .set_dump_reason(DumpReason {
+            exception_code: 0x5741_5443,
+            reason: "watchdog: main thread hung for 30s".into(),
+            parameters: parameters.clone(),
+        })
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let data = std::fs::read(tmpfile.path()).unwrap();
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+
+    let exception: MinidumpException = dump.get_stream().expect("missing exception");
+    let record = &exception.raw.exception_record;
+    assert_eq!(record.exception_code, 0x5741_5443);
+    assert_eq!(record.number_parameters, 15);
+    assert_eq!(record.exception_information[..], parameters[..15]);
+
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxDumpReason.into())
+        .expect("missing dump reason stream");
+    let header: MDRawDumpReason = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(header.exception_code, 0x5741_5443);
+    assert_eq!(header.number_of_parameters, 20);
+    let written: Vec<u64> = (0..header.number_of_parameters as usize)
+        .map(|i| {
+            stream
+                .pread_with(header.size_of_header as usize + i * 8, scroll::LE)
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(written, parameters);
+
+    assert_eq!(
+        read_minidump_string(&data, header.reason_rva),
+        "watchdog: main thread hung for 30s"
+    );
+}
+
+#[test]
+fn rust_panic() {
+    use minidump_writer::ext_streams::{ExtStreamType, MDRawRustPanic, EXCEPTION_CODE_RUST_PANIC};
+    use scroll::Pread;
+
+    let tmpfile = tempfile::Builder::new()
+        .prefix("rust_panic")
+        .tempfile()
+        .unwrap();
+
+    let mut child = start_child_and_return(&["panic_dump", tmpfile.path().to_str().unwrap()]);
+    let status = child.wait().expect("Failed to wait on child");
+    assert!(status.success());
+
+    let data = std::fs::read(tmpfile.path()).unwrap();
+    let read_string = |rva: u32| read_minidump_string(&data, rva);
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxRustPanic.into())
+        .expect("missing rust panic stream");
+    let rust_panic: MDRawRustPanic = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(read_string(rust_panic.message_rva), "the answer is 42");
+    assert!(read_string(rust_panic.file_rva).ends_with("test.rs"));
+    assert_ne!(rust_panic.line, 0);
+    assert_eq!(read_string(rust_panic.thread_name_rva), "panicking");
+    assert!(read_string(rust_panic.backtrace_rva).contains("panic_dump"));
+
+    let exception: MinidumpException = dump.get_stream().expect("missing exception");
+    assert_eq!(
+        exception.raw.exception_record.exception_code,
+        EXCEPTION_CODE_RUST_PANIC
+    );
+    assert_eq!(exception.get_crashing_thread_id(), rust_panic.thread_id);
+
+    let threads: MinidumpThreadList = dump.get_stream().expect("missing thread list");
+    assert!(threads
+        .threads
+        .iter()
+        .any(|thread| thread.raw.thread_id == rust_panic.thread_id));
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn stack_overflow() {
+    use minidump_writer::ext_streams::{
+        ExtStreamType, MDRawStackOverflow, MDRawStackOverflowList,
+        STACK_OVERFLOW_FLAG_FAULT_ADDRESS, STACK_OVERFLOW_FLAG_STACK_POINTER,
+    };
+    use scroll::Pread;
+
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("stack_overflow")
+        .tempfile()
+        .unwrap();
+
+    let maps = std::fs::read_to_string(format!("/proc/{pid}/maps")).unwrap();
+    let stack_start = maps
+        .lines()
+        .find(|line| line.ends_with("[stack]"))
+        .and_then(|line| line.split('-').next())
+        .map(|start| u64::from_str_radix(start, 16).unwrap())
+        .expect("no stack mapping");
+
+    // The main thread overflowed into the gap below its stack
+    let mut crash_context = get_crash_context(pid);
+    crash_context.inner.context.uc_mcontext.gregs[libc::REG_RSP as usize] =
+        (stack_start - 128) as i64;
+    crash_context.inner.siginfo.ssi_signo = libc::SIGSEGV as u32;
+    crash_context.inner.siginfo.ssi_addr = stack_start - 136;
+
+    MinidumpWriter::new(pid, pid)
+        .set_crash_context(crash_context)
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxStackOverflow.into())
+        .expect("missing stack overflow stream");
+    let header: MDRawStackOverflowList = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(header.number_of_entries, 1);
+    let overflow: MDRawStackOverflow = stream
+        .pread_with(header.size_of_header as usize, scroll::LE)
+        .unwrap();
+    assert_eq!(overflow.thread_id, pid as u32);
+    assert_eq!(
+        overflow.flags,
+        STACK_OVERFLOW_FLAG_STACK_POINTER | STACK_OVERFLOW_FLAG_FAULT_ADDRESS
+    );
+    assert_eq!(overflow.stack_pointer, stack_start - 128);
+    assert_eq!(overflow.fault_address, stack_start - 136);
+    assert_eq!(overflow.guard_end, stack_start);
+    assert!(overflow.guard_start < stack_start - 136);
+
+    // The deepest part of the stack is in the dump
+    let memory: MinidumpMemoryList = dump.get_stream().expect("missing memory list");
+    assert!(memory.memory_at_address(stack_start).is_some());
+}
+
+#[test]
+fn register_memory() {
+    use minidump_writer::register_memory::RegisterMemoryPolicy;
+
+    let mut child = start_child_and_return(&["spawn_alloc_wait"]);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("register_memory")
+        .tempfile()
+        .unwrap();
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let mut output = buf.split_whitespace();
+    let memory_addr = usize::from_str_radix(output.next().unwrap().trim_start_matches("0x"), 16)
+        .expect("unable to parse mmap_addr");
+
+    // The child faulted in the middle of its allocation
+    let fault_address = memory_addr + 512;
+    let mut crash_context = get_crash_context(pid);
+    crash_context.inner.siginfo.ssi_signo = libc::SIGSEGV as u32;
+    crash_context.inner.siginfo.ssi_addr = fault_address as u64;
+
+    MinidumpWriter::new(pid, pid)
+        .set_crash_context(CrashContext {
+            inner: crash_context.inner.clone(),
+        })
+        .set_register_memory_policy(RegisterMemoryPolicy::default())
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    // No window fits within a size limit of 0
+    let mut limited_tmpfile = tempfile::Builder::new()
+        .prefix("register_memory_limited")
+        .tempfile()
+        .unwrap();
+    MinidumpWriter::new(pid, pid)
+        .set_crash_context(crash_context)
+        .set_register_memory_policy(RegisterMemoryPolicy::default())
+        .set_minidump_size_limit(0)
+        .dump(&mut limited_tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let window_start = fault_address - 128;
+    let dump = Minidump::read_path(limited_tmpfile.path()).expect("Failed to read minidump");
+    let memory_list: MinidumpMemoryList = dump.get_stream().expect("missing memory list");
+    assert!(memory_list.memory_at_address(window_start as u64).is_none());
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let memory_list: MinidumpMemoryList = dump.get_stream().expect("missing memory list");
+    let region = memory_list
+        .memory_at_address(window_start as u64)
+        .expect("missing the memory around the fault address");
+    assert!(region.base_address + region.size >= (fault_address + 128) as u64);
+    let offset = window_start - region.base_address as usize;
+    let expected: Vec<u8> = (384..640).map(|idx| (idx % 255) as u8).collect();
+    assert_eq!(&region.bytes[offset..offset + 256], &expected[..]);
+}
+
+#[test]
+fn memory_rules() {
+    use minidump_writer::memory_rules::MemoryRule;
+
+    let mut child = start_child_and_return(&["spawn_alloc_wait"]);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("memory_rules")
+        .tempfile()
+        .unwrap();
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let mut output = buf.split_whitespace();
+    let memory_addr = u64::from_str_radix(output.next().unwrap().trim_start_matches("0x"), 16)
+        .expect("unable to parse mmap_addr");
+    let memory_size: usize = output
+        .next()
+        .unwrap()
+        .parse()
+        .expect("unable to parse memory_size");
+
+    MinidumpWriter::new(pid, pid)
+        .set_memory_rules(vec![MemoryRule::name("[heap]", usize::MAX)])
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let memory_list: MinidumpMemoryList = dump.get_stream().expect("missing memory list");
+    let region = memory_list
+        .memory_at_address(memory_addr)
+        .expect("missing the heap");
+    let offset = (memory_addr - region.base_address) as usize;
+    let expected: Vec<u8> = (0..memory_size).map(|idx| (idx % 255) as u8).collect();
+    assert_eq!(&region.bytes[offset..offset + memory_size], &expected[..]);
+}
+
+#[test]
+fn data_segments() {
+    use minidump_writer::data_segments::DataSegmentsPolicy;
+
+    let mut child = start_child_and_return(&["spawn_global_wait"]);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("data_segments")
+        .tempfile()
+        .unwrap();
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let global_addr = u64::from_str_radix(buf.trim().trim_start_matches("0x"), 16)
+        .expect("unable to parse the address of the global");
+
+    // The global is also application memory, which is only written once
+    MinidumpWriter::new(pid, pid)
+        .set_data_segments_policy(DataSegmentsPolicy::default())
+        .set_app_memory(vec![AppMemory {
+            ptr: global_addr as usize,
+            length: 8,
+        }])
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let memory_list: MinidumpMemoryList = dump.get_stream().expect("missing memory list");
+    let region = memory_list
+        .memory_at_address(global_addr)
+        .expect("missing the data segments of the executable");
+    let offset = (global_addr - region.base_address) as usize;
+    let global = u64::from_ne_bytes(region.bytes[offset..offset + 8].try_into().unwrap());
+    assert_eq!(global, 0x0123_4567_89ab_cdef);
+
+    let mut ranges: Vec<_> = memory_list
+        .iter()
+        .map(|region| region.base_address..region.base_address + region.size)
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    for pair in ranges.windows(2) {
+        assert!(
+            pair[0].end <= pair[1].start,
+            "{:x?} overlaps {:x?}",
+            pair[0],
+            pair[1]
+        );
+    }
+
+    // Nothing is included for other modules without a pattern
+    let modules: MinidumpModuleList = dump.get_stream().expect("missing module list");
+    let libc = modules
+        .iter()
+        .find(|module| module.name.contains("libc.so"))
+        .expect("missing libc");
+    assert!(!memory_list.iter().any(|region| {
+        region.base_address >= libc.raw.base_of_image
+            && region.base_address < libc.raw.base_of_image + libc.raw.size_of_image as u64
+    }));
+}
+
+#[cfg(all(target_arch = "x86_64", target_env = "gnu"))]
+#[test]
+fn thread_pointer() {
+    let mut child = start_child_and_return(&["spawn_thread_pointer_wait"]);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("thread_pointer")
+        .tempfile()
+        .unwrap();
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let thread_pointer = u64::from_str_radix(buf.trim().trim_start_matches("0x"), 16)
+        .expect("unable to parse the thread pointer");
+
+    MinidumpWriter::new(pid, pid)
+        .set_thread_pointer_window(4096)
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let thread_list: MinidumpThreadList = dump.get_stream().expect("missing thread list");
+    let main_thread = thread_list
+        .get_thread(pid as u32)
+        .expect("missing the main thread");
+    assert_eq!(main_thread.raw.teb, thread_pointer);
+
+    // The thread control block starts with a pointer to itself
+    let memory_list: MinidumpMemoryList = dump.get_stream().expect("missing memory list");
+    let region = memory_list
+        .memory_at_address(thread_pointer)
+        .expect("missing the memory around the thread pointer");
+    let offset = (thread_pointer - region.base_address) as usize;
+    let tcb = u64::from_ne_bytes(region.bytes[offset..offset + 8].try_into().unwrap());
+    assert_eq!(tcb, thread_pointer);
+}
+
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn signal_frame() {
+    use minidump_writer::ext_streams::{ExtStreamType, MDRawSignalFrame, MDRawSignalFrameList};
+    use scroll::Pread;
+
+    let mut child = start_child_and_return(&["spawn_signal_handler_wait"]);
+    let pid = child.id() as i32;
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let mut output = buf.split_whitespace();
+    let altstack_start: u64 = output.next().unwrap().parse().unwrap();
+    let altstack_size: u64 = output.next().unwrap().parse().unwrap();
+    let altstack = altstack_start..altstack_start + altstack_size;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("signal_frame")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .recover_signal_frames()
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxSignalFrames.into())
+        .expect("missing signal frames stream");
+    let header: MDRawSignalFrameList = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(header.number_of_entries, 1);
+    let frame: MDRawSignalFrame = stream
+        .pread_with(header.size_of_header as usize, scroll::LE)
+        .unwrap();
+    assert_eq!(frame.thread_id, pid as u32);
+    assert_eq!(frame.signal, libc::SIGUSR1 as u32);
+    assert!(altstack.contains(&frame.handler_stack_pointer));
+    assert!(altstack.contains(&frame.frame_address));
+
+    // The thread is recorded on its own stack, where `raise` was called
+    let threads: MinidumpThreadList = dump.get_stream().expect("missing thread list");
+    let thread = threads
+        .threads
+        .iter()
+        .find(|thread| thread.raw.thread_id == pid as u32)
+        .expect("missing main thread");
+    let context = thread
+        .context(&dump.get_stream().unwrap(), None)
+        .expect("missing thread context");
+    let stack_pointer = context.get_stack_pointer();
+    assert!(!altstack.contains(&stack_pointer));
+    assert_eq!(
+        thread.raw.stack.start_of_memory_range,
+        stack_pointer & !0xfff
+    );
+
+    // Both stacks are in the dump
+    let memory: MinidumpMemoryList = dump.get_stream().expect("missing memory list");
+    assert!(memory.memory_at_address(stack_pointer).is_some());
+    assert!(memory
+        .memory_at_address(frame.handler_stack_pointer)
+        .is_some());
+}
+
+#[cfg(not(target_arch = "mips"))]
+#[test]
+fn signal_stack() {
+    use minidump_writer::ext_streams::{ExtStreamType, MDRawSignalStack};
+    use scroll::Pread;
+
+    let mut child = start_child_and_return(&["spawn_signal_handler_wait"]);
+    let pid = child.id() as i32;
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let mut output = buf.split_whitespace();
+    let altstack_start: u64 = output.next().unwrap().parse().unwrap();
+    let altstack_size: u64 = output.next().unwrap().parse().unwrap();
+    let altstack = altstack_start..altstack_start + altstack_size;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("signal_stack")
+        .tempfile()
+        .unwrap();
+
+    // The handler crashed the thread on the alternate signal stack
+    let mut crash_context = get_crash_context(pid);
+    let uc_stack = &mut crash_context.inner.context.uc_stack;
+    uc_stack.ss_sp = altstack_start as _;
+    uc_stack.ss_flags = 0;
+    uc_stack.ss_size = altstack_size as usize;
+
+    MinidumpWriter::new(pid, pid)
+        .set_crash_context(crash_context)
+        .capture_signal_stack()
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxSignalStack.into())
+        .expect("missing signal stack stream");
+    let signal_stack: MDRawSignalStack = stream.pread_with(0, scroll::LE).unwrap();
+    assert_eq!(signal_stack.thread_id, pid as u32);
+    assert_eq!(signal_stack.ss_sp, altstack_start);
+    assert_eq!(signal_stack.ss_size, altstack_size);
+    assert!(altstack.contains(&signal_stack.handler_stack_pointer));
+    assert_eq!(
+        signal_stack.start_of_memory_range,
+        signal_stack.handler_stack_pointer
+    );
+    assert_eq!(
+        signal_stack.start_of_memory_range + signal_stack.data_size as u64,
+        altstack.end
+    );
+
+    let memory: MinidumpMemoryList = dump.get_stream().expect("missing memory list");
+    let block = memory
+        .memory_at_address(signal_stack.handler_stack_pointer)
+        .expect("missing signal stack memory");
+    assert_eq!(block.desc.memory.rva, signal_stack.rva);
+}
+
+#[test]
+fn anonymous_elf_module() {
+    use minidump_writer::ext_streams::MODULE_FLAG_ANONYMOUS;
+
+    let mut child = start_child_and_return(&["spawn_anon_elf_wait"]);
+    let pid = child.id() as i32;
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let mut output = buf.split_whitespace();
+    let mmap_addr: u64 = output
+        .next()
+        .unwrap()
+        .parse()
+        .expect("unable to parse mmap_addr");
+    let memory_size: u64 = output
+        .next()
+        .unwrap()
+        .parse()
+        .expect("unable to parse memory_size");
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("anonymous_elf_module")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let module_list: MinidumpModuleList = dump
+        .get_stream()
+        .expect("Couldn't find stream MinidumpModuleList");
+    let module = module_list
+        .module_at_address(mmap_addr)
+        .expect("the anonymous image is not in the module list");
+    assert_eq!(module.raw.base_of_image, mmap_addr);
+    assert_eq!(module.raw.size_of_image as u64, memory_size);
+
+    // The image is a copy of the start of the child's own executable
+    let main_module = module_list
+        .main_module()
+        .expect("Could not get main module");
+    let expected_id = main_module.code_identifier().unwrap();
+    assert_eq!(module.code_identifier().unwrap(), expected_id);
+    assert_eq!(module.code_file(), format!("anonymous-elf-{expected_id}"));
+
+    let info = read_module_infos(&dump)
+        .into_iter()
+        .find(|info| info.base_of_image == mmap_addr)
+        .expect("missing info of the anonymous module");
+    assert_ne!(info.flags & MODULE_FLAG_ANONYMOUS, 0);
+}
+
+#[test]
+fn module_list_from_link_map() {
+    use minidump_writer::{
+        ext_streams::{MODULE_FLAG_LINK_MAP, MODULE_FLAG_PRELOADED},
+        minidump_writer::ModuleSource,
+    };
+
+    let num_of_threads = 1;
+    let path: String = if let Ok(p) = std::env::var("TEST_HELPER") {
+        p
+    } else {
+        std::env!("CARGO_BIN_EXE_test").into()
+    };
+
+    // The test binary links libc anyway, preloading it only changes how the
+    // dynamic linker finds it
+    let mut child = Command::new(path)
+        .env("RUST_BACKTRACE", "1")
+        .env("LD_PRELOAD", "libc.so.6")
+        .arg("spawn_and_wait")
+        .arg(num_of_threads.to_string())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to execute child");
+    wait_for_threads(&mut child, num_of_threads);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("module_list_from_link_map")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .set_module_source(ModuleSource::LinkMap)
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let soft_errors = read_minidump_soft_errors_or_panic(&dump).to_string();
+    assert!(
+        !soft_errors.contains("LinkMapUnavailable"),
+        "link_map wasn't read: {soft_errors}"
+    );
+
+    let module_list: MinidumpModuleList = dump
+        .get_stream()
+        .expect("Couldn't find stream MinidumpModuleList");
+    let main_module = module_list
+        .main_module()
+        .expect("Could not get main module");
+
+    let infos = read_module_infos(&dump);
+    assert_eq!(infos.len(), module_list.iter().count());
+
+    for (module, info) in module_list.iter().zip(&infos) {
+        assert_eq!(module.raw.base_of_image, info.base_of_image);
+        assert_ne!(
+            info.flags & MODULE_FLAG_LINK_MAP,
+            0,
+            "{} isn't in the link_map",
+            module.code_file()
+        );
+        let is_libc = module.code_file().contains("libc.so");
+        assert_eq!(info.flags & MODULE_FLAG_PRELOADED != 0, is_libc);
+    }
+    assert!(module_list
+        .iter()
+        .any(|module| module.code_file().contains("libc.so")));
+
+    // The test binary is position independent, so its load bias is its base
+    let main_info = infos
+        .iter()
+        .find(|info| info.base_of_image == main_module.raw.base_of_image)
+        .unwrap();
+    assert_eq!(main_info.load_bias, main_module.raw.base_of_image);
+}
+
+#[test]
+fn module_list_from_link_map_skipped_mapping() {
+    use minidump_writer::minidump_writer::ModuleSource;
+
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    // Describe libc with a user mapping, so that its own mapping is left out
+    // of the module list
+    let dumper = PtraceDumper::new_report_soft_errors(
+        pid,
+        minidump_writer::minidump_writer::STOP_TIMEOUT,
+        Default::default(),
+        error_graph::ErrorList::default(),
+    )
+    .expect("Couldn't init dumper");
+    let mapping = dumper
+        .mappings
+        .iter()
+        .find(|mapping| {
+            mapping.is_executable()
+                && mapping
+                    .name
+                    .as_deref()
+                    .is_some_and(|name| name.to_string_lossy().contains("libc.so"))
+        })
+        .expect("missing mapping of libc")
+        .clone();
+    drop(dumper);
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("module_list_from_link_map_skipped_mapping")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .set_module_source(ModuleSource::LinkMap)
+        .set_user_mapping_list(vec![MappingEntry {
+            mapping,
+            identifier: vec![0x11; 16],
+        }])
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let soft_errors = read_minidump_soft_errors_or_panic(&dump).to_string();
+    assert!(
+        !soft_errors.contains("LinkMapUnavailable"),
+        "link_map wasn't read: {soft_errors}"
+    );
+    assert!(
+        !soft_errors.contains("LinkMapEntryNotMapped"),
+        "link_map entries weren't matched: {soft_errors}"
+    );
+}
+
+#[test]
+fn module_metadata_memory() {
+    let num_of_threads = 1;
+    let mut child = start_child_and_wait_for_threads(num_of_threads);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("module_metadata_memory")
+        .tempfile()
+        .unwrap();
+
+    use minidump_writer::jit_code::JitCodeRegion;
+
+    // Modules without an ELF file are left out
+    let jit_code = JitCodeRegion {
+        start_address: 0x10000,
+        size: 0x1000,
+        name: "metadata-jit".into(),
+        symbols: Vec::new(),
+    };
+    let user_mapping = MappingInfo {
+        start_address: 0x20000,
+        size: 0x1000,
+        offset: 0,
+        permissions: MMPermissions::READ | MMPermissions::EXECUTE,
+        name: Some("a fake mapping".into()),
+        deleted: false,
+        device: (0, 0),
+        inode: 0,
+        first_mapping_end: 0,
+        system_mapping_info: SystemMappingInfo {
+            start_address: 0x20000,
+            end_address: 0x21000,
+        },
+    };
+
+    MinidumpWriter::new(pid, pid)
+        .capture_module_metadata()
+        .set_jit_code(vec![jit_code])
+        .set_user_mapping_list(vec![MappingEntry {
+            mapping: user_mapping,
+            identifier: vec![0x11; 16],
+        }])
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    // No metadata fits within a size limit of 0
+    let mut limited_tmpfile = tempfile::Builder::new()
+        .prefix("module_metadata_memory_limited")
+        .tempfile()
+        .unwrap();
+    MinidumpWriter::new(pid, pid)
+        .capture_module_metadata()
+        .set_minidump_size_limit(0)
+        .dump(&mut limited_tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(limited_tmpfile.path()).expect("Failed to read minidump");
+    let module_list: MinidumpModuleList = dump
+        .get_stream()
+        .expect("Couldn't find stream MinidumpModuleList");
+    let memory_list: MinidumpMemoryList<'_> = dump
+        .get_stream()
+        .expect("Couldn't find stream MinidumpMemoryList");
+    for module in module_list.iter() {
+        assert!(memory_list
+            .memory_at_address(module.base_address())
+            .is_none());
+    }
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let soft_errors = read_minidump_soft_errors_or_panic(&dump).to_string();
+    assert!(
+        !soft_errors.contains("WriteModuleMetadataErrors"),
+        "{soft_errors}"
+    );
+
+    let module_list: MinidumpModuleList = dump
+        .get_stream()
+        .expect("Couldn't find stream MinidumpModuleList");
+    let memory_list: MinidumpMemoryList<'_> = dump
+        .get_stream()
+        .expect("Couldn't find stream MinidumpMemoryList");
+
+    for module in module_list
+        .iter()
+        .filter(|module| ![0x10000, 0x20000].contains(&module.base_address()))
+    {
+        let header = memory_list
+            .memory_at_address(module.base_address())
+            .unwrap_or_else(|| panic!("no ELF header for {}", module.code_file()));
+        assert_eq!(header.base_address, module.base_address());
+        assert_eq!(&header.bytes[..4], b"\x7fELF");
+    }
+
+    // The build id can be read again from the captured memory alone
+    let main_module = module_list
+        .main_module()
+        .expect("Could not get main module");
+    let base = main_module.base_address();
+    let mut image = vec![0u8; main_module.size() as usize];
+    for block in memory_list.iter() {
+        if block.base_address >= base && block.base_address < base + main_module.size() {
+            let offset = (block.base_address - base) as usize;
+            let end = (offset + block.bytes.len()).min(image.len());
+            image[offset..end].copy_from_slice(&block.bytes[..end - offset]);
+        }
+    }
+    let BuildId(build_id) = BuildId::read_from_module(image.as_slice().into())
+        .expect("Failed to get build_id from the captured memory");
+    let expected_id: String = build_id.iter().map(|b| format!("{b:02x}")).collect();
+    assert_eq!(main_module.code_identifier().unwrap().as_str(), expected_id);
+}
+
+#[test]
+fn module_file_identity() {
+    use minidump_writer::ext_streams::{MODULE_FLAG_FILE_HASH, MODULE_FLAG_NO_BUILD_ID};
+    use std::os::unix::fs::MetadataExt;
+
+    fn fnv1a(data: &[u8]) -> u64 {
+        data.iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    // Code without a build id, in a directory that allows executable mappings
+    let dir = tempfile::Builder::new()
+        .prefix("module_file_identity")
+        .tempdir_in(env!("CARGO_TARGET_TMPDIR"))
+        .unwrap();
+    let code_path = dir.path().join("code.bin");
+    let code: Vec<u8> = (0..2 * 4096).map(|i| (i % 251) as u8).collect();
+    std::fs::write(&code_path, &code).unwrap();
+
+    let mut child = start_child_and_return(&["map_exec_file_wait", code_path.to_str().unwrap()]);
+    wait_for_threads(&mut child, 1);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("module_file_identity")
+        .tempfile()
+        .unwrap();
+
+    let mut hashed_tmpfile = tempfile::Builder::new()
+        .prefix("module_file_identity")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+    MinidumpWriter::new(pid, pid)
+        .hash_module_files()
+        .dump(&mut hashed_tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let metadata = std::fs::metadata(&code_path).unwrap();
+    for (path, hashed) in [(tmpfile.path(), false), (hashed_tmpfile.path(), true)] {
+        let dump = Minidump::read_path(path).expect("Failed to read minidump");
+        let module_list: MinidumpModuleList = dump
+            .get_stream()
+            .expect("Couldn't find stream MinidumpModuleList");
+        let infos = read_module_infos(&dump);
+
+        let info_of = |module: &MinidumpModule| {
+            *infos
+                .iter()
+                .find(|info| info.base_of_image == module.raw.base_of_image)
+                .unwrap()
+        };
+
+        // The module without a build id is kept, and identified by its file
+        let module = module_list
+            .iter()
+            .find(|module| module.code_file() == code_path.to_string_lossy())
+            .expect("the module without a build id is missing");
+        let info = info_of(module);
+        assert_ne!(info.flags & MODULE_FLAG_NO_BUILD_ID, 0);
+        assert_eq!(info.inode, metadata.ino());
+        assert_eq!(info.file_size, code.len() as u64);
+        assert_eq!(info.file_mtime, metadata.mtime());
+        assert_eq!(info.flags & MODULE_FLAG_FILE_HASH != 0, hashed);
+        assert_eq!(info.file_hash, if hashed { fnv1a(&code) } else { 0 });
+
+        // Modules with a build id are identified by their file as well
+        let main_module = module_list
+            .main_module()
+            .expect("Could not get main module");
+        let info = info_of(main_module);
+        assert_eq!(info.flags & MODULE_FLAG_NO_BUILD_ID, 0);
+        assert_ne!(info.inode, 0);
+        assert_ne!(info.file_size, 0);
+        assert_ne!(info.file_mtime, 0);
+    }
+}
+
+#[test]
+fn module_debug_links() {
+    use minidump_writer::module_reader::DebugLinks;
+
+    /// A little endian ELF64 file whose only sections are the debug links,
+    /// padded to two pages so that its mapping is big enough to be a module.
+    fn elf_with_debug_links(debuglink: &[u8], debugaltlink: &[u8]) -> Vec<u8> {
+        let shstrtab = b"\0.shstrtab\0.gnu_debuglink\0.gnu_debugaltlink\0";
+        // Name offset, type and contents of the sections after the null one
+        let sections: [(u32, u32, &[u8]); 3] =
+            [(1, 3, shstrtab), (11, 1, debuglink), (26, 1, debugaltlink)];
+
+        let mut elf = vec![0u8; 64];
+        let mut headers = vec![0u8; 64];
+        for (name, kind, contents) in sections {
+            elf.resize(elf.len().next_multiple_of(4), 0);
+            let offset = elf.len() as u64;
+            elf.extend_from_slice(contents);
+
+            headers.extend_from_slice(&name.to_le_bytes());
+            headers.extend_from_slice(&kind.to_le_bytes());
+            headers.extend_from_slice(&[0; 16]); // flags, address
+            headers.extend_from_slice(&offset.to_le_bytes());
+            headers.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+            headers.extend_from_slice(&[0; 8]); // link, info
+            headers.extend_from_slice(&1u64.to_le_bytes()); // alignment
+            headers.extend_from_slice(&[0; 8]); // entry size
+        }
+        elf.resize(elf.len().next_multiple_of(8), 0);
+        let section_headers_offset = elf.len() as u64;
+        elf.extend_from_slice(&headers);
+        elf.resize(2 * 4096, 0);
+
+        elf[..7].copy_from_slice(b"\x7fELF\x02\x01\x01");
+        elf[16..18].copy_from_slice(&3u16.to_le_bytes()); // ET_DYN
+        elf[18..20].copy_from_slice(&62u16.to_le_bytes()); // EM_X86_64
+        elf[20..24].copy_from_slice(&1u32.to_le_bytes()); // EV_CURRENT
+        elf[40..48].copy_from_slice(&section_headers_offset.to_le_bytes());
+        elf[52..54].copy_from_slice(&64u16.to_le_bytes()); // ELF header size
+        elf[58..60].copy_from_slice(&64u16.to_le_bytes()); // section header size
+        elf[60..62].copy_from_slice(&4u16.to_le_bytes()); // section count
+        elf[62..64].copy_from_slice(&1u16.to_le_bytes()); // .shstrtab index
+        elf
+    }
+
+    // A module with known debug links, in a directory that allows executable
+    // mappings
+    let dir = tempfile::Builder::new()
+        .prefix("module_debug_links")
+        .tempdir_in(env!("CARGO_TARGET_TMPDIR"))
+        .unwrap();
+    let module_path = dir.path().join("libdebuglinks.so");
+    std::fs::write(
+        &module_path,
+        elf_with_debug_links(
+            b"libdebuglinks.so.debug\0\0\x78\x56\x34\x12",
+            b"/usr/lib/debug/.dwz/debuglinks.debug\0\xde\xad\xbe\xef",
+        ),
+    )
+    .unwrap();
+
+    let mut child = start_child_and_return(&["map_exec_file_wait", module_path.to_str().unwrap()]);
+    wait_for_threads(&mut child, 1);
+    let pid = child.id() as i32;
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("module_debug_links")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let data = std::fs::read(tmpfile.path()).unwrap();
+    let read_string = |rva: u32| (rva != 0).then(|| read_minidump_string(&data, rva));
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let module_list: MinidumpModuleList = dump
+        .get_stream()
+        .expect("Couldn't find stream MinidumpModuleList");
+    let infos = read_module_infos(&dump);
+
+    let (_, info) = module_list
+        .iter()
+        .zip(&infos)
+        .find(|(module, _)| module.code_file() == module_path.to_string_lossy())
+        .expect("the module with debug links is missing");
+    assert_eq!(
+        read_string(info.debuglink_rva).as_deref(),
+        Some("libdebuglinks.so.debug")
+    );
+    assert_eq!(info.debuglink_crc, 0x12345678);
+    assert_eq!(
+        read_string(info.debugaltlink_rva).as_deref(),
+        Some("/usr/lib/debug/.dwz/debuglinks.debug")
+    );
+    assert_eq!(
+        read_string(info.debugaltlink_build_id_rva).as_deref(),
+        Some("deadbeef")
+    );
+
+    // Every module has the debug links of its file
+    for (module, info) in module_list.iter().zip(&infos) {
+        let Ok(links) = DebugLinks::read_from_file(std::path::Path::new(&*module.code_file()))
+        else {
+            assert_eq!(info.debuglink_rva, 0);
+            assert_eq!(info.debugaltlink_rva, 0);
+            continue;
+        };
+        assert_eq!(
+            links.debuglink,
+            read_string(info.debuglink_rva).map(|name| (name, info.debuglink_crc))
+        );
+        assert_eq!(
+            links.debugaltlink.map(|(name, build_id)| (
+                name,
+                build_id.iter().map(|b| format!("{b:02x}")).collect()
+            )),
+            read_string(info.debugaltlink_rva).zip(read_string(info.debugaltlink_build_id_rva))
+        );
+    }
+}
+
+#[test]
+fn jit_code_modules() {
+    use minidump_writer::{
+        ext_streams::{ExtStreamType, MDRawJitSymbol, MDRawJitSymbolList, MODULE_FLAG_JIT},
+        jit_code::{JitCodeRegion, JitSymbol},
+    };
+    use scroll::Pread;
+
+    let mut child = start_child_and_return(&["spawn_jit_code_wait"]);
+    let pid = child.id() as i32;
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read addresses provided by child");
+    let mut output = buf
+        .split_whitespace()
+        .map(|value| value.parse::<usize>().expect("unable to parse output"));
+    let perf_code = output.next().unwrap();
+    let other_code = output.next().unwrap();
+    let page_size = output.next().unwrap();
+    let perf_map = format!("/tmp/perf-{pid}.map");
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("jit_code_modules")
+        .tempfile()
+        .unwrap();
+
+    // The second page of the other mapping is registered by the caller
+    let caller_code = other_code + page_size;
+    let result = MinidumpWriter::new(pid, pid)
+        .ingest_jit_code_info()
+        .set_jit_code(vec![JitCodeRegion {
+            start_address: caller_code,
+            size: page_size,
+            name: "caller-jit".into(),
+            symbols: vec![JitSymbol {
+                address: caller_code + 0x10,
+                size: 0x20,
+                name: "jit_caller_func".into(),
+            }],
+        }])
+        .dump(&mut tmpfile);
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+    let _ = std::fs::remove_file(&perf_map);
+    result.expect("Could not write minidump");
+
+    let data = std::fs::read(tmpfile.path()).unwrap();
+    let read_string = |rva: u32| read_minidump_string(&data, rva);
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+    let module_list: MinidumpModuleList = dump
+        .get_stream()
+        .expect("Couldn't find stream MinidumpModuleList");
+    let infos = read_module_infos(&dump);
+
+    for (start_address, name) in [
+        (perf_code, format!("jit-code-{perf_code:x}")),
+        (other_code, format!("jit-code-{other_code:x}")),
+        (caller_code, "caller-jit".to_owned()),
+    ] {
+        let module = module_list
+            .module_at_address(start_address as u64)
+            .unwrap_or_else(|| panic!("missing JIT module {name}"));
+        assert_eq!(module.raw.base_of_image, start_address as u64);
+        assert_eq!(module.raw.size_of_image as usize, page_size);
+        assert_eq!(module.code_file(), name);
+        let info = infos
+            .iter()
+            .find(|info| info.base_of_image == start_address as u64)
+            .expect("missing module info");
+        assert_ne!(info.flags & MODULE_FLAG_JIT, 0);
+    }
+
+    // Regions already covered by a module, or by another region, are left out
+    let mut ranges: Vec<_> = module_list
+        .iter()
+        .map(|module| {
+            module.raw.base_of_image..module.raw.base_of_image + module.raw.size_of_image as u64
+        })
+        .collect();
+    ranges.sort_by_key(|range| range.start);
+    for pair in ranges.windows(2) {
+        assert!(
+            pair[0].end <= pair[1].start,
+            "{:x?} overlaps {:x?}",
+            pair[0],
+            pair[1]
+        );
+    }
+
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxJitSymbols.into())
+        .expect("missing JIT symbols stream");
+    let header: MDRawJitSymbolList = stream.pread_with(0, scroll::LE).unwrap();
+    let symbols: Vec<(u64, u64, String)> = (0..header.number_of_entries as usize)
+        .map(|i| {
+            let offset = header.size_of_header as usize + i * header.size_of_entry as usize;
+            let symbol: MDRawJitSymbol = stream.pread_with(offset, scroll::LE).unwrap();
+            (symbol.address, symbol.size, read_string(symbol.name_rva))
+        })
+        .collect();
+    for symbol in [
+        (perf_code as u64, 0x40, "jit_perf_func".to_owned()),
+        (other_code as u64, 0x80, "jit_gdb_func".to_owned()),
+        (
+            caller_code as u64 + 0x10,
+            0x20,
+            "jit_caller_func".to_owned(),
+        ),
+    ] {
+        assert!(symbols.contains(&symbol), "{symbol:?} not in {symbols:?}");
+    }
+    assert!(!symbols
+        .iter()
+        .any(|(_, _, name)| name == "jit_perf_covered_func"));
+}
+
+#[test]
+fn sanitization_policy() {
+    use minidump_writer::{
+        ext_streams::{
+            ExtStreamType, MDRawSanitizationStatsList, MDRawSanitizedRegion, SanitizedRegionKind,
+        },
+        sanitization::{SanitizationPolicy, ScrubMode},
+    };
+    use scroll::Pread;
+
+    let mut child = start_child_and_return(&["spawn_alloc_wait"]);
+    let pid = child.id() as i32;
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let mut output = buf.split_whitespace();
+    let memory_addr = usize::from_str_radix(output.next().unwrap().trim_start_matches("0x"), 16)
+        .expect("unable to parse mmap_addr");
+    let memory_size: usize = output
+        .next()
+        .unwrap()
+        .parse()
+        .expect("unable to parse memory_size");
+
+    let mut tmpfile = tempfile::Builder::new()
+        .prefix("sanitization_policy")
+        .tempfile()
+        .unwrap();
+
+    MinidumpWriter::new(pid, pid)
+        .set_app_memory(vec![AppMemory {
+            ptr: memory_addr,
+            length: memory_size,
+        }])
+        .set_sanitization_policy(SanitizationPolicy {
+            scrub_mode: ScrubMode::Zero,
+            sanitize_app_memory: true,
+            sanitize_registers: true,
+            ..Default::default()
+        })
+        .dump(&mut tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    let dump = Minidump::read_path(tmpfile.path()).expect("Failed to read minidump");
+
+    // The application memory holds no pointers nor small integers
+    let memory_list: MinidumpMemoryList =
+        dump.get_stream().expect("Couldn't find MinidumpMemoryList");
+    let region = memory_list
+        .memory_at_address(memory_addr as u64)
+        .expect("Couldn't find memory region");
+    assert_eq!(region.bytes, vec![0u8; memory_size]);
+
+    let stream = dump
+        .get_raw_stream(ExtStreamType::LinuxSanitizationStats.into())
+        .expect("missing sanitization stats stream");
+    let header: MDRawSanitizationStatsList = stream.pread_with(0, scroll::LE).unwrap();
+    let regions: Vec<MDRawSanitizedRegion> = (0..header.number_of_entries as usize)
+        .map(|i| {
+            let offset = header.size_of_header as usize + i * header.size_of_entry as usize;
+            stream.pread_with(offset, scroll::LE).unwrap()
+        })
+        .collect();
+
+    let app_memory = regions
+        .iter()
+        .find(|region| region.kind == SanitizedRegionKind::AppMemory as u32)
+        .expect("missing stats of the application memory");
+    assert_eq!(app_memory.start_of_memory_range, memory_addr as u64);
+    assert_eq!(app_memory.data_size, memory_size as u64);
+    assert_eq!(app_memory.scrub_mode, 1);
+    assert_eq!(
+        app_memory.scrubbed_words,
+        (memory_size / std::mem::size_of::<usize>()) as u64
+    );
+
+    let thread_list: MinidumpThreadList =
+        dump.get_stream().expect("Couldn't find MinidumpThreadList");
+    for thread in &thread_list.threads {
+        let stack = regions
+            .iter()
+            .find(|region| {
+                region.kind == SanitizedRegionKind::Stack as u32
+                    && region.start_of_memory_range == thread.raw.stack.start_of_memory_range
+            })
+            .expect("missing stats of a stack");
+        assert_eq!(stack.data_size, thread.raw.stack.memory.data_size as u64);
+
+        // Unwinding still works with sanitized registers
+        let context = thread
+            .context(&dump.get_stream().unwrap(), None)
+            .expect("missing thread context");
+        let stack_pointer = context.get_stack_pointer();
+        assert!(
+            stack_pointer >= thread.raw.stack.start_of_memory_range
+                && stack_pointer < stack.start_of_memory_range + stack.data_size
+        );
+        let modules: MinidumpModuleList = dump.get_stream().unwrap();
+        assert!(modules
+            .module_at_address(context.get_instruction_pointer())
+            .is_some());
+        assert_eq!(
+            stack.kept_small_ints + stack.kept_pointers + stack.scrubbed_words,
+            (stack.data_size - stack.zeroed_bytes) / std::mem::size_of::<usize>() as u64
+        );
+    }
+}
+
+#[test]
+fn dump_twice() {
+    use minidump_writer::{
+        ext_streams::ExtStreamType,
+        jit_code::{JitCodeRegion, JitSymbol},
+    };
+
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    let mut first_tmpfile = tempfile::Builder::new()
+        .prefix("dump_twice")
+        .tempfile()
+        .unwrap();
+    let mut second_tmpfile = tempfile::Builder::new()
+        .prefix("dump_twice")
+        .tempfile()
+        .unwrap();
+
+    let mut writer = MinidumpWriter::new(pid, pid);
+    writer.sanitize_stack().set_jit_code(vec![JitCodeRegion {
+        start_address: 0x10000,
+        size: 0x1000,
+        name: "twice-jit".into(),
+        symbols: vec![JitSymbol {
+            address: 0x10010,
+            size: 0x20,
+            name: "jit_twice_func".into(),
+        }],
+    }]);
+    writer
+        .dump(&mut first_tmpfile)
+        .expect("Could not write minidump");
+    writer
+        .dump(&mut second_tmpfile)
+        .expect("Could not write minidump");
+
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+
+    // The second dump has nothing left over from the first one
+    let first = Minidump::read_path(first_tmpfile.path()).expect("Failed to read minidump");
+    let second = Minidump::read_path(second_tmpfile.path()).expect("Failed to read minidump");
+    let first_memory: MinidumpMemoryList = first.get_stream().expect("missing memory list");
+    let second_memory: MinidumpMemoryList = second.get_stream().expect("missing memory list");
+    assert_eq!(first_memory.iter().count(), second_memory.iter().count());
+    for stream_type in [
+        ExtStreamType::LinuxModuleInfo,
+        ExtStreamType::LinuxJitSymbols,
+        ExtStreamType::LinuxSanitizationStats,
+    ] {
+        assert_eq!(
+            first.get_raw_stream(stream_type.into()).unwrap().len(),
+            second.get_raw_stream(stream_type.into()).unwrap().len(),
+        );
+    }
+}
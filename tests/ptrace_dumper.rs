@@ -411,3 +411,165 @@ fn test_sanitize_stack_copy() {
     assert_eq!(waitres.code(), None);
     assert_eq!(status, Signal::SIGKILL as i32);
 }
+
+#[test]
+fn test_sanitization_policy() {
+    use minidump_writer::sanitization::{SanitizationPolicy, ScrubMode};
+
+    let mut child = start_child_and_return(&["spawn_alloc_wait"]);
+    let pid = child.id() as i32;
+
+    let mut f = BufReader::new(child.stdout.as_mut().expect("Can't open stdout"));
+    let mut buf = String::new();
+    let _ = f
+        .read_line(&mut buf)
+        .expect("Couldn't read address provided by child");
+    let mut output = buf.split_whitespace();
+    let heap_addr = usize::from_str_radix(output.next().unwrap().trim_start_matches("0x"), 16)
+        .expect("unable to parse mmap_addr");
+
+    let mut dumper = assert_no_soft_errors!(
+        soft_errors,
+        PtraceDumper::new_report_soft_errors(
+            pid,
+            minidump_writer::minidump_writer::STOP_TIMEOUT,
+            Default::default(),
+            &mut soft_errors,
+        )
+    )
+    .expect("Couldn't init dumper");
+    assert_no_soft_errors!(soft_errors, dumper.suspend_threads(&mut soft_errors));
+
+    let thread_info = dumper
+        .get_thread_info_by_index(0)
+        .expect("Couldn't find thread_info");
+
+    let mut policy = SanitizationPolicy {
+        scrub_mode: ScrubMode::Zero,
+        ..Default::default()
+    };
+    let junk = "abcdefghijklmnop".as_bytes();
+    let mut memory = [
+        &heap_addr.to_ne_bytes(),
+        &junk[0..size_of::<usize>()],
+        &[1, 2][..],
+    ]
+    .concat();
+
+    // Heap pointers are scrubbed, unless the heap is allowed. Scrubbed words
+    // are zeroed rather than marked.
+    let mut sanitized = memory.clone();
+    let stats = dumper
+        .sanitize_memory(&policy, &mut sanitized, thread_info.stack_pointer, 0)
+        .expect("Failed to sanitize");
+    assert_eq!(sanitized, vec![0u8; memory.len()]);
+    assert_eq!(stats.scrubbed_words, 2);
+    assert_eq!(stats.kept_pointers, 0);
+    assert_eq!(stats.zeroed_bytes, 2);
+
+    policy.allow_range(heap_addr..heap_addr + 1);
+    let stats = dumper
+        .sanitize_memory(&policy, &mut memory, thread_info.stack_pointer, 0)
+        .expect("Failed to sanitize");
+    assert_eq!(memory[0..size_of::<usize>()], heap_addr.to_ne_bytes());
+    assert_eq!(stats.scrubbed_words, 1);
+    assert_eq!(stats.kept_pointers, 1);
+
+    // Negative small integers are only kept on request
+    let mut memory = (-42isize).to_ne_bytes();
+    let stats = dumper
+        .sanitize_memory(&policy, &mut memory, thread_info.stack_pointer, 0)
+        .expect("Failed to sanitize");
+    assert_eq!(stats.kept_small_ints, 0);
+    assert_eq!(stats.scrubbed_words, 1);
+
+    policy.keep_small_negative_ints = true;
+    let mut memory = (-42isize).to_ne_bytes();
+    let stats = dumper
+        .sanitize_memory(&policy, &mut memory, thread_info.stack_pointer, 0)
+        .expect("Failed to sanitize");
+    assert_eq!(memory, (-42isize).to_ne_bytes());
+    assert_eq!(stats.kept_small_ints, 1);
+
+    // Registers follow the same rules, except for the ones needed to unwind
+    #[cfg(target_arch = "x86_64")]
+    {
+        use minidump_writer::minidump_cpu::RawContextCPU;
+        use scroll::{Pread, Pwrite};
+
+        let junk = u64::from_ne_bytes(junk[0..8].try_into().unwrap());
+        let code = thread_info.get_instruction_pointer() as u64;
+        let mut cpu = RawContextCPU {
+            rax: 42,
+            rbx: junk,
+            rcx: code,
+            rdx: heap_addr as u64,
+            rsi: thread_info.stack_pointer as u64,
+            rsp: junk,
+            rbp: junk,
+            rip: junk,
+            ..Default::default()
+        };
+        let mut float_save = minidump_writer::minidump_cpu::FloatStateCPU::default();
+        float_save.xmm_registers[0] = (junk as u128) << 64 | 42;
+        cpu.float_save
+            .pwrite_with(float_save, 0, scroll::Endian::Little)
+            .unwrap();
+
+        policy.allowed_ranges.clear();
+        dumper.sanitize_cpu_context(&policy, &mut cpu, thread_info.stack_pointer);
+        assert_eq!(cpu.rax, 42);
+        assert_eq!(cpu.rbx, 0);
+        assert_eq!(cpu.rcx, code);
+        assert_eq!(cpu.rdx, 0);
+        assert_eq!(cpu.rsi, thread_info.stack_pointer as u64);
+        assert_eq!((cpu.rsp, cpu.rbp, cpu.rip), (junk, junk, junk));
+        let float_save: minidump_writer::minidump_cpu::FloatStateCPU = cpu
+            .float_save
+            .pread_with(0, scroll::Endian::Little)
+            .unwrap();
+        assert_eq!(float_save.xmm_registers[0], 42);
+    }
+
+    assert_no_soft_errors!(soft_errors, dumper.resume_threads(&mut soft_errors));
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait for child");
+}
+
+#[test]
+fn test_mapping_file_paths() {
+    let mut child = start_child_and_wait_for_threads(1);
+    let pid = child.id() as i32;
+
+    let dumper = assert_no_soft_errors!(
+        soft_errors,
+        PtraceDumper::new_report_soft_errors(
+            pid,
+            minidump_writer::minidump_writer::STOP_TIMEOUT,
+            Default::default(),
+            &mut soft_errors,
+        )
+    )
+    .expect("Couldn't init dumper");
+
+    let exe = std::fs::read_link(format!("/proc/{pid}/exe")).unwrap();
+    let mapping = dumper
+        .mappings
+        .iter()
+        .find(|mapping| mapping.name.as_deref() == Some(exe.as_os_str()))
+        .expect("missing mapping of the executable");
+
+    // Files are resolved in the mount namespace of the process
+    let paths = mapping.file_paths(pid);
+    let root_path = paths.last().unwrap();
+    assert!(root_path.starts_with(format!("/proc/{pid}/root")));
+    assert!(root_path.ends_with(exe.strip_prefix("/").unwrap()));
+    assert!(mapping.open_file(pid).is_ok());
+
+    // Threads in our own namespace need no translation
+    assert!(dumper.threads.iter().all(|thread| thread.ns_tid.is_none()));
+
+    drop(dumper);
+    child.kill().expect("Failed to kill process");
+    child.wait().expect("Failed to wait on killed process");
+}